@@ -0,0 +1,44 @@
+extern crate adventofcode;
+use adventofcode::d10::Map;
+use std::time::Instant;
+
+// A pseudo-random map with roughly `percent` of its cells filled.
+fn generate(size: usize, percent: u64) -> Vec<String> {
+    let mut state = 1u64;
+    (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6_364_136_223_846_793_005)
+                        .wrapping_add(1_442_695_040_888_963_407);
+                    if (state >> 33) % 100 < percent {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn time(label: &str, map: Map) {
+    let start = Instant::now();
+    let best = map.best_station();
+    println!("{:>24}: {:?} ({:?})", label, start.elapsed(), best);
+}
+
+fn main() {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    for &(size, percent) in &[(40, 50), (140, 50), (200, 100)] {
+        let lines = generate(size, percent);
+        let asteroids: usize = lines.iter().map(|l| l.matches('#').count()).sum();
+        let map = || Map::new(lines.iter().cloned());
+        time(&format!("{} asteroids", asteroids), map().with_threads(1));
+        time(
+            &format!("{} asteroids, {} threads", asteroids, threads),
+            map(),
+        );
+    }
+}
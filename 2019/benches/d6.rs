@@ -0,0 +1,73 @@
+extern crate adventofcode;
+use adventofcode::d6::{Graph, Parser};
+use std::time::Instant;
+
+const OBJECTS: usize = 1_000_000;
+
+fn name(i: usize) -> String {
+    match i {
+        0 => "COM".to_string(),
+        _ => format!("O{:05X}", i),
+    }
+}
+
+// A generated orbit map in which each object orbits a pseudo-randomly chosen earlier one, with
+// an occasional long chain, like the deeper parts of a real input.
+fn orbits() -> Vec<String> {
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    (1..OBJECTS)
+        .map(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let parent = if i % 64 == 0 {
+                (state as usize) % i
+            } else {
+                i - 1
+            };
+            format!("{}){}", name(parent), name(i))
+        })
+        .collect()
+}
+
+fn time<T, F: FnOnce() -> T>(label: &str, f: F) -> T {
+    let start = Instant::now();
+    let result = f();
+    println!("{:>12}: {:?}", label, start.elapsed());
+    result
+}
+
+fn main() {
+    let lines = orbits();
+    let map = time("parse", || Parser::parse(&lines).unwrap());
+    let g = time("build", || Graph::new(map).unwrap());
+    let total = time("traverse", || g.traverse().values().sum::<usize>());
+    assert_eq!(time("total", || g.total_orbits()), total);
+    let pairs: Vec<_> = (0..1000)
+        .map(|i| (name(OBJECTS - 1 - i), name(OBJECTS / 2 + i * 37)))
+        .collect();
+    let distance = time("distance", || {
+        pairs
+            .iter()
+            .map(|(a, b)| g.distance(a, b).unwrap())
+            .sum::<usize>()
+    });
+    let path = time("path", || {
+        pairs
+            .iter()
+            .map(|(a, b)| g.path_between(a, b).unwrap().len())
+            .sum::<usize>()
+    });
+    let refs: Vec<_> = pairs
+        .iter()
+        .map(|(a, b)| (a.as_str(), b.as_str()))
+        .collect();
+    let batch = time("distances", || {
+        g.distances(&refs)
+            .into_iter()
+            .map(|d| d.unwrap())
+            .sum::<usize>()
+    });
+    assert_eq!(distance, batch);
+    println!("{} orbits, {} steps, {} on paths", total, distance, path);
+}
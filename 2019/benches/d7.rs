@@ -0,0 +1,107 @@
+extern crate adventofcode;
+use adventofcode::d2;
+use adventofcode::d7::{Chain, Network, Objective, Outcome, Searcher};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+
+const ROUNDS: usize = 20;
+
+const FEEDBACK: &str = "3,52,1001,52,-5,52,3,53,1,52,56,54,1007,54,5,55,1005,55,26,1001,54,-5,54,1105,1,12,1,53,54,53,1008,54,0,55,1001,55,1,55,2,53,55,53,4,53,1001,56,-1,56,1005,56,6,99,0,0,0,0,10";
+
+// The search as it was before the program was parsed once per search: the settings come from
+// Heap's algorithm as the permutator crate implemented it, every run parses the program again, and
+// every outcome is sorted to find the best.
+mod previous {
+    use super::*;
+
+    // Every ordering of `inputs`, starting with `inputs` itself, since Heap's algorithm only
+    // yields the orderings after the first.
+    fn phases(inputs: &[i64]) -> Vec<Vec<i64>> {
+        let mut data = inputs.to_vec();
+        let mut c = vec![0; data.len()];
+        let mut i = 0;
+        let mut v = vec![data.clone()];
+        while i < data.len() {
+            if c[i] < i {
+                if i % 2 == 0 {
+                    data.swap(0, i);
+                } else {
+                    data.swap(c[i], i);
+                }
+                c[i] += 1;
+                i = 0;
+                v.push(data.clone());
+            } else {
+                c[i] = 0;
+                i += 1;
+            }
+        }
+        v
+    }
+
+    fn signal(prog: &str, phases: Vec<i64>) -> Result<i64, d2::Error> {
+        let code = d2::Parser::parse(prog);
+        let v = Network::feedback_loop(phases.len()).run(&[Arc::new(code)], &phases, 0)?;
+        v.last().copied().ok_or(d2::Error::OutOfData)
+    }
+
+    pub fn best(prog: &str, inputs: &[i64]) -> Result<Option<Outcome>, d2::Error> {
+        let mut v = phases(inputs)
+            .into_iter()
+            .map(|phases| {
+                let signal = signal(prog, phases.clone())?;
+                Ok(Outcome { phases, signal })
+            })
+            .collect::<Result<Vec<_>, d2::Error>>()?;
+        v.sort_by(|a, b| {
+            let key = |o: &Outcome| (-(o.signal as i128), o.phases.clone());
+            key(a).cmp(&key(b))
+        });
+        Ok(v.into_iter().next())
+    }
+}
+
+fn time<F: FnMut() -> i64>(label: &str, mut f: F) {
+    let start = Instant::now();
+    let mut result = 0;
+    for _ in 0..ROUNDS {
+        result = f();
+    }
+    println!(
+        "{:>16}: {:?} per search ({})",
+        label,
+        start.elapsed() / ROUNDS as u32,
+        result
+    );
+}
+
+fn main() {
+    // Real puzzle inputs are a few hundred values long, most of which a given run never touches;
+    // pad the example out to a similar size.
+    let prog = format!("{}{}", FEEDBACK, ",0".repeat(500));
+    let inputs = vec![5, 6, 7, 8, 9];
+
+    time("previous", || {
+        previous::best(&prog, &inputs).unwrap().unwrap().signal
+    });
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut threads = vec![1, 2, 4, cores];
+    threads.sort_unstable();
+    threads.dedup();
+    for threads in threads {
+        let label = match threads {
+            1 => "1 thread".to_string(),
+            _ => format!("{} threads", threads),
+        };
+        time(&label, || {
+            Searcher::new(Chain::new(&prog, 0), inputs.clone())
+                .with_feedback()
+                .with_threads(threads)
+                .best(Objective::Max)
+                .unwrap()
+                .unwrap()
+                .signal
+        });
+    }
+}
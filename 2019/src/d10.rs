@@ -2,17 +2,16 @@ extern crate adventofcode;
 use adventofcode::d10::{Map, Point};
 use std::io;
 use std::io::BufRead;
+use std::process;
 
 fn best_point(map: &Map) -> (Point, usize) {
-    map.visible_from()
-        .iter()
-        .fold((Point(0, 0), 0), |(p, pcount), (&q, &qcount)| {
-            if qcount > pcount {
-                (q, qcount)
-            } else {
-                (p, pcount)
-            }
-        })
+    match map.best_station() {
+        Some(best) => best,
+        None => {
+            eprintln!("error: the map has no asteroids");
+            process::exit(1);
+        }
+    }
 }
 
 fn destroyed_from(map: &Map, point: &Point) -> Vec<Point> {
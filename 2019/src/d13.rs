@@ -5,7 +5,7 @@ use std::io;
 use std::io::BufRead;
 
 fn count_for(inp: &str, typ: i64) -> usize {
-    let mut a = Arcade::new(Parser::parse(&inp));
+    let mut a = Arcade::new(Parser::parse(inp));
     a.run();
     a.square_count()[&typ]
 }
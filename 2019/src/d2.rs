@@ -7,7 +7,7 @@ use std::rc::Rc;
 
 fn program_for(inp: &str, noun: i64, verb: i64) -> i64 {
     let v: Vec<i64> = Vec::new();
-    let mut p = Program::new(Parser::parse(&inp));
+    let mut p = Program::new(Parser::parse(inp));
     p[1] = noun;
     p[2] = verb;
     p.run(Rc::new(RefCell::new(v.into_iter())));
@@ -1,32 +1,67 @@
 extern crate adventofcode;
 use adventofcode::d3::{Graph, Parser};
+use std::env;
+use std::fs::File;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
+use std::process;
 
-fn distance(paths: Vec<String>) -> i64 {
-    let mut g = Graph::new(paths.iter().map(|p| Parser::parse(&p)).collect());
-    g.run();
-    g.intersections()
-        .map(|(p, _)| p.0.abs() + p.1.abs())
-        .filter(|&v| v > 0)
-        .min()
-        .unwrap()
+fn usage() -> ! {
+    eprintln!("usage: d3 [--report] [--render FILE.svg|FILE.ppm]");
+    process::exit(2);
 }
 
-fn steps(paths: Vec<String>) -> usize {
-    let mut g = Graph::new(paths.iter().map(|p| Parser::parse(&p)).collect());
-    g.run();
-    g.intersections()
-        .map(|(_, steps)| steps)
-        .filter(|&v| v > 0)
-        .min()
-        .unwrap()
+fn render(g: &Graph, path: &str) -> io::Result<()> {
+    let mut f = io::BufWriter::new(File::create(path)?);
+    if path.ends_with(".ppm") {
+        g.write_ppm(&mut f, 1000)?;
+    } else {
+        g.write_svg(&mut f)?;
+    }
+    f.flush()
+}
+
+fn report(g: &Graph, wires: usize) {
+    for c in g.crossings() {
+        println!("{}", c);
+    }
+    for i in 0..wires {
+        for (p, steps) in g.self_crossings(i) {
+            println!(
+                "wire {} crosses itself at {},{} after {:?} steps",
+                i, p.0, p.1, steps
+            );
+        }
+    }
 }
 
 fn main() -> io::Result<()> {
+    let mut output = None;
+    let mut verbose = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--render" => output = Some(args.next().unwrap_or_else(|| usage())),
+            "--report" => verbose = true,
+            _ => usage(),
+        }
+    }
+
     let b = io::BufReader::new(io::stdin());
     let v = b.lines().collect::<Result<Vec<String>, io::Error>>()?;
-    println!("{}", distance(v.clone()));
-    println!("{}", steps(v));
+    let paths = v
+        .iter()
+        .map(|p| Parser::parse(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut g = Graph::new(paths);
+    g.run();
+    println!("{}", g.closest_by_distance().unwrap().1);
+    println!("{}", g.closest_by_steps().unwrap().1);
+    if verbose {
+        report(&g, v.len());
+    }
+    if let Some(path) = output {
+        render(&g, &path)?;
+    }
     Ok(())
 }
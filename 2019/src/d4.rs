@@ -1,9 +1,46 @@
 extern crate adventofcode;
-use adventofcode::d4::Filter;
+use adventofcode::d4::{Parser, Policy};
+use std::env;
 use std::io;
+use std::io::BufRead;
+use std::process;
+
+fn usage() -> ! {
+    eprintln!("usage: d4 [--matches] [--histogram] [p1|p2|SPEC]...");
+    process::exit(2);
+}
 
 fn main() -> io::Result<()> {
-    println!("{}", (240_298..784_956).filter(|&n| Filter::validate_p1(n)).count());
-    println!("{}", (240_298..784_956).filter(|&n| Filter::validate_p2(n)).count());
+    let mut matches = false;
+    let mut histogram = false;
+    let mut policies = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--matches" => matches = true,
+            "--histogram" => histogram = true,
+            _ if arg.starts_with('-') => usage(),
+            _ => policies.push(Parser::policy(&arg)?),
+        }
+    }
+    if policies.is_empty() {
+        policies = vec![Policy::p1(), Policy::p2()];
+    }
+
+    let b = io::BufReader::new(io::stdin());
+    let line = b.lines().next().unwrap_or_else(|| usage())?;
+    let range = Parser::range(&line)?;
+    for p in &policies {
+        println!("{}", p.count(range.clone()));
+        if matches {
+            for n in p.candidates(range.clone()) {
+                println!("{}", n);
+            }
+        }
+        if histogram {
+            for (rule, n) in p.rejections(range.clone()) {
+                println!("{:>8} {}", n, rule);
+            }
+        }
+    }
     Ok(())
 }
@@ -6,7 +6,7 @@ use std::io::BufRead;
 use std::rc::Rc;
 
 fn program_for(inp: &str, v: Vec<i64>) -> Vec<i64> {
-    let mut p = Program::new(Parser::parse(&inp));
+    let mut p = Program::new(Parser::parse(inp));
     p.run(Rc::new(RefCell::new(v.into_iter())))
         .collect::<Result<Vec<_>, Error>>()
         .unwrap()
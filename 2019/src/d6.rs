@@ -1,23 +1,65 @@
 extern crate adventofcode;
 use adventofcode::d6::{Graph, Parser};
+use std::env;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
+use std::process;
 
-fn total_orbits(orbits: &[String]) -> usize {
-    let orbits = Parser::parse(orbits);
-    let counts = Graph::new(orbits).traverse();
-    counts.iter().map(|(_, v)| *v).sum::<usize>()
+fn usage() -> ! {
+    eprintln!("usage: d6 [--systems] [--dot|--tree] [--highlight A,B]");
+    process::exit(2);
 }
 
-fn transfers_between(orbits: &[String]) -> usize {
-    let orbits = Parser::parse(orbits);
-    Graph::new(orbits).transfers_between("YOU", "SAN") - 2
+fn total_orbits(g: &Graph) -> usize {
+    g.total_orbits()
+}
+
+fn transfers_between(g: &Graph) -> usize {
+    g.transfers("YOU", "SAN").unwrap()
 }
 
 fn main() -> io::Result<()> {
+    let mut systems = false;
+    let mut format = None;
+    let mut highlight = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--systems" => systems = true,
+            "--dot" | "--tree" => format = Some(arg),
+            "--highlight" => highlight = Some(args.next().unwrap_or_else(|| usage())),
+            _ => usage(),
+        }
+    }
+    let highlight = highlight.map(|h| match h.find(',') {
+        Some(i) => (h[..i].to_string(), h[i + 1..].to_string()),
+        None => usage(),
+    });
+    let highlight = highlight.as_ref().map(|(a, b)| (a.as_str(), b.as_str()));
+
     let b = io::BufReader::new(io::stdin());
     let v = b.lines().collect::<Result<Vec<String>, io::Error>>()?;
-    println!("{}", total_orbits(&v));
-    println!("{}", transfers_between(&v));
+    let orbits = Parser::parse(&v)?;
+    if systems {
+        for s in Graph::forest(orbits)?.systems() {
+            println!(
+                "{}: {} objects, {} orbits, depth {}",
+                s.root, s.objects, s.orbits, s.depth
+            );
+        }
+        return Ok(());
+    }
+    if let Some(format) = format {
+        let g = Graph::forest(orbits)?;
+        let mut out = io::BufWriter::new(io::stdout());
+        match format.as_str() {
+            "--dot" => g.write_dot(&mut out, highlight)?,
+            _ => g.write_tree(&mut out, highlight)?,
+        }
+        return out.flush();
+    }
+    let g = Graph::new(orbits)?;
+    println!("{}", total_orbits(&g));
+    println!("{}", transfers_between(&g));
     Ok(())
 }
@@ -1,26 +1,51 @@
 extern crate adventofcode;
-use adventofcode::d7::{Chain, Searcher};
+use adventofcode::d7::{Chain, Objective, Searcher};
+use std::env;
 use std::io;
 use std::io::BufRead;
+use std::process;
 
-fn program_for(inp: &str) -> i64 {
-    let inputs = vec![0, 1, 2, 3, 4];
-    let s = Searcher::new(Chain::new(inp, 0), inputs);
-    s.outputs().iter().cloned().max().unwrap()
+fn usage() -> ! {
+    eprintln!("usage: d7 [--min | --target N] [--table]");
+    process::exit(2);
 }
 
-fn feedback_program_for(inp: &str) -> i64 {
-    let inputs = vec![5, 6, 7, 8, 9];
-    let s = Searcher::new(Chain::new(inp, 0), inputs);
-    s.outputs_with_feedback().iter().cloned().max().unwrap()
+fn report(s: &Searcher, objective: Objective, table: bool) -> io::Result<()> {
+    let ranked = s.ranked(objective)?;
+    if let Some(best) = ranked.first() {
+        println!("{} {:?}", best.signal, best.phases);
+    }
+    if table {
+        for o in ranked {
+            println!("{:?} {}", o.phases, o.signal);
+        }
+    }
+    Ok(())
 }
 
 fn main() -> io::Result<()> {
+    let mut objective = Objective::Max;
+    let mut table = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--min" => objective = Objective::Min,
+            "--target" => {
+                let n = args.next().and_then(|n| n.parse().ok());
+                objective = Objective::Target(n.unwrap_or_else(|| usage()));
+            }
+            "--table" => table = true,
+            _ => usage(),
+        }
+    }
+
     let b = io::BufReader::new(io::stdin());
     if let Some(inp) = b.lines().next() {
         let inp = &inp?;
-        println!("{:?}", program_for(inp));
-        println!("{:?}", feedback_program_for(inp));
+        let s = Searcher::new(Chain::new(inp, 0), vec![0, 1, 2, 3, 4]);
+        report(&s, objective, table)?;
+        let s = Searcher::new(Chain::new(inp, 0), vec![5, 6, 7, 8, 9]).with_feedback();
+        report(&s, objective, table)?;
     }
     Ok(())
 }
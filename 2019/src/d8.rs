@@ -1,32 +1,88 @@
 extern crate adventofcode;
 use adventofcode::d8::Image;
+use std::env;
 use std::io;
-use std::io::BufRead;
+use std::io::{BufRead, Write};
+use std::process;
 
-fn min_zero_layer(inp: &[u8]) -> usize {
-    let img = Image::new(25, 6, inp);
-    img.iter()
-        .map(|l| {
-            let summary = l.summary();
-            (summary[&b'0'], summary[&b'1'] * summary[&b'2'])
-        })
-        .min()
-        .unwrap()
-        .1
+fn usage() -> ! {
+    eprintln!("usage: d8 [--size WxH] [--format text|pbm|pgm|png|svg] [--scale N]");
+    process::exit(2);
 }
 
-fn image(inp: &[u8]) -> String {
-    let img = Image::new(25, 6, inp);
+fn min_zero_layer(img: &Image) -> usize {
+    img.checksum().unwrap_or(0)
+}
+
+fn image(img: &Image) -> String {
     String::from_utf8(img.image()).unwrap()
 }
 
+// The letters drawn in the picture, if it can be read.  This is only a convenience, so a picture
+// which isn't text is reported rather than treated as an error.
+fn text(img: &Image) -> Option<String> {
+    match img.text() {
+        Ok(r) => {
+            if !r.is_complete() {
+                eprintln!("warning: unrecognised letters at {:?}", r.unknown);
+            }
+            Some(r.text)
+        }
+        Err(e) => {
+            eprintln!("note: can't read letters: {}", e);
+            None
+        }
+    }
+}
+
+fn size(arg: Option<String>) -> Option<(usize, usize)> {
+    let arg = arg?;
+    let i = arg.find('x')?;
+    Some((arg[..i].parse().ok()?, arg[i + 1..].parse().ok()?))
+}
+
 fn main() -> io::Result<()> {
+    let (mut width, mut height) = (25, 6);
+    let mut format = String::from("text");
+    let mut scale = 1;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--size" => {
+                let (w, h) = size(args.next()).unwrap_or_else(|| usage());
+                width = w;
+                height = h;
+            }
+            "--format" => format = args.next().unwrap_or_else(|| usage()),
+            "--scale" => {
+                scale = match args.next().and_then(|s| s.parse().ok()) {
+                    Some(n) if n > 0 => n,
+                    _ => usage(),
+                }
+            }
+            _ => usage(),
+        }
+    }
+
     let b = io::BufReader::new(io::stdin());
     if let Some(inp) = b.lines().next() {
-        let inp = &inp?;
-        let bytes: Vec<u8> = inp.bytes().collect();
-        println!("{:?}", min_zero_layer(&bytes));
-        println!("{}", image(&bytes));
+        let img = Image::new(width, height, inp?.as_bytes())?;
+        if format != "text" {
+            let mut out = io::BufWriter::new(io::stdout());
+            match format.as_str() {
+                "pbm" => img.write_pbm(&mut out, scale)?,
+                "pgm" => img.write_pgm(&mut out, scale)?,
+                "png" => img.write_png(&mut out, scale)?,
+                "svg" => img.write_svg(&mut out, scale)?,
+                _ => usage(),
+            }
+            return out.flush();
+        }
+        println!("{:?}", min_zero_layer(&img));
+        println!("{}", image(&img));
+        if let Some(text) = text(&img) {
+            println!("{}", text);
+        }
     }
     Ok(())
 }
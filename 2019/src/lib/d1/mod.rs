@@ -17,7 +17,7 @@ pub fn fuel_for_module_recursive(mass: u64) -> u64 {
 
 pub fn parse_file(f: Box<dyn io::BufRead>) -> Vec<u64> {
     f.lines()
-        .map(|l| u64::from_str_radix(&l.unwrap(), 10).unwrap())
+        .map(|l| l.unwrap().parse::<u64>().unwrap())
         .collect()
 }
 
@@ -1,169 +1,119 @@
-use itertools::Itertools;
-use std::cmp::Ordering;
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::hash::{Hash, Hasher};
+use std::cmp::{self, Ordering};
+use std::collections::{BTreeMap, HashMap};
+use std::thread;
 
-#[derive(Debug)]
-struct ComparableFloat(f64);
+mod visibility;
 
-impl PartialEq for ComparableFloat {
-    fn eq(&self, other: &Self) -> bool {
-        (self.0 - other.0).abs() < 0.000_000_1
+// Stein's binary algorithm, since this is called for every pair of points and division is slow.
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    if a == 0 || b == 0 {
+        return (a | b) as i64;
     }
-}
-
-impl Eq for ComparableFloat {}
-
-impl Ord for ComparableFloat {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.eq(other) {
-            Ordering::Equal
-        } else if self.0 < other.0 {
-            Ordering::Less
-        } else {
-            Ordering::Greater
+    let shift = (a | b).trailing_zeros();
+    a >>= a.trailing_zeros();
+    loop {
+        b >>= b.trailing_zeros();
+        if a > b {
+            std::mem::swap(&mut a, &mut b);
+        }
+        b -= a;
+        if b == 0 {
+            return (a << shift) as i64;
         }
     }
 }
 
-impl PartialOrd for ComparableFloat {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+// The direction from one point to another, as the offset between them divided by its greatest
+// common divisor.  Points along the same ray from a station therefore share a direction, and
+// everything is exact integer arithmetic.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct Direction {
+    dx: i64,
+    dy: i64,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-pub struct Point(pub usize, pub usize);
-
-impl Point {
-    fn line(&self, other: &Point) -> Line {
-        let m = (other.1 as f64 - self.1 as f64) / (other.0 as f64 - self.0 as f64);
-        let b = self.1 as f64 - (self.0 as f64 * m);
-        Line {
-            m,
-            b,
-            x: if other.0 == self.0 {
-                Some(self.0)
-            } else {
-                None
+impl Direction {
+    fn new(dx: i64, dy: i64) -> Self {
+        match gcd(dx, dy) {
+            0 => panic!("no direction from a point to itself"),
+            1 => Direction { dx, dy },
+            g => Direction {
+                dx: dx / g,
+                dy: dy / g,
             },
         }
     }
 
-    fn polar_to(&self, other: &Point) -> PolarCoordinates {
-        let pi = 4.0 * 1.0f64.atan();
-        let dx = other.0 as isize - self.0 as isize;
-        let dy = self.1 as isize - other.1 as isize;
-        let dsq = (dx * dx + dy * dy) as usize;
-        let val = (dy as f64 / dx as f64).atan();
-        let theta = match (dy.signum(), dx.signum()) {
-            (0, 1) => 0.0,
-            (0, 0) => unreachable!(),
-            (0, -1) => pi,
-            (1, 1) => val,
-            (1, 0) => pi / 2.0,
-            (1, -1) => val + pi,
-            (-1, 1) => val + 2.0 * pi,
-            (-1, 0) => 3.0 * pi / 2.0,
-            (-1, -1) => val + pi,
-            _ => unreachable!(),
-        };
-        PolarCoordinates { dsq, theta }
+    // Whether the direction is in the half turn clockwise from straight up, including straight up
+    // but not straight down.  Remember that y increases downwards.
+    fn first_half(&self) -> bool {
+        self.dx > 0 || (self.dx == 0 && self.dy < 0)
     }
 }
 
-#[derive(Copy, Clone, Debug)]
-pub struct Line {
-    m: f64,
-    b: f64,
-    x: Option<usize>,
-}
-
-impl PartialEq for Line {
-    fn eq(&self, other: &Self) -> bool {
-        match (self.x, other.x) {
-            (Some(a), Some(b)) => a == b,
-            (Some(_), None) => false,
-            (None, Some(_)) => false,
-            (None, None) => {
-                ComparableFloat(self.m) == ComparableFloat(other.m)
-                    && ComparableFloat(self.b) == ComparableFloat(other.b)
-            }
-        }
+// Directions are ordered clockwise starting from straight up, which is the order the laser
+// sweeps them in.  Within each half turn, one direction comes before another exactly when the
+// cross product of the two is positive.
+impl Ord for Direction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.first_half().cmp(&self.first_half()).then_with(|| {
+            let cross = self.dx * other.dy - self.dy * other.dx;
+            0.cmp(&cross)
+        })
     }
 }
 
-impl Eq for Line {}
-
-impl Hash for Line {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.x.hash(state);
-        match self.x {
-            Some(_) => (),
-            None => {
-                self.m.to_bits().hash(state);
-                self.b.to_bits().hash(state);
-            }
-        }
+impl PartialOrd for Direction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-// This is a slightly modified version of polar coordinates in that we use the
-// distance squared instead of the distance.  This simplifies things since floats
-// are not comparable but integers are.
-#[derive(Copy, Clone, Debug)]
-struct PolarCoordinates {
-    dsq: usize,
-    theta: f64,
-}
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Point(pub usize, pub usize);
 
-impl PolarCoordinates {
-    // We adjust theta for sorting such that the value will end up always positive and increasing
-    // in the clockwise direction, starting at pi/2 (pointing directly up).
-    fn adjusted_theta(&self) -> f64 {
-        let pi = 4.0 * 1.0f64.atan();
-        let circ = 2.0 * pi;
-        (circ - (self.theta - (pi / 2.0))) % circ
+impl Point {
+    fn offset_to(&self, other: &Point) -> (i64, i64) {
+        (
+            other.0 as i64 - self.0 as i64,
+            other.1 as i64 - self.1 as i64,
+        )
     }
-}
 
-impl PartialEq for PolarCoordinates {
-    fn eq(&self, other: &Self) -> bool {
-        if self.dsq != other.dsq {
-            return false;
-        }
-        ComparableFloat(self.theta) == ComparableFloat(other.theta)
+    fn direction_to(&self, other: &Point) -> Direction {
+        let (dx, dy) = self.offset_to(other);
+        Direction::new(dx, dy)
     }
-}
-
-impl Eq for PolarCoordinates {}
 
-impl Ord for PolarCoordinates {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match ComparableFloat(self.adjusted_theta()).cmp(&ComparableFloat(other.adjusted_theta())) {
-            Ordering::Equal => self.dsq.cmp(&other.dsq),
-            x => x,
-        }
-    }
-}
-
-impl PartialOrd for PolarCoordinates {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    fn distance_sq(&self, other: &Point) -> i64 {
+        let (dx, dy) = self.offset_to(other);
+        dx * dx + dy * dy
     }
 }
 
 pub struct Map {
+    // The points in reading order, which the visibility counts depend on.
     points: Vec<Point>,
+    threads: usize,
 }
 
 impl Map {
+    /// Reads a map, which uses as many threads as there are processors to count visible points.
     pub fn new<F: Iterator<Item = String>>(iter: F) -> Self {
         Map {
             points: Self::parse(iter),
+            threads: thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
         }
     }
 
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = cmp::max(threads, 1);
+        self
+    }
+
     fn parse<F: Iterator<Item = String>>(iter: F) -> Vec<Point> {
         iter.enumerate()
             .flat_map(|(j, s)| {
@@ -176,65 +126,30 @@ impl Map {
     }
 
     pub fn visible_from(&self) -> HashMap<Point, usize> {
-        // The algorithm is as follows.  We compare each point with each other point.  If the
-        // second point is on the same line and in the same direction as another point, then only
-        // one of those points is visible.  We therefore use the pair (line, ordering) as a
-        // unique key to de-duplicate to find the number of other points visible from this one.
         self.points
             .iter()
-            .map(|p| {
-                (
-                    *p,
-                    self.points
-                        .iter()
-                        .filter(|q| *q != p)
-                        .map(|q| (p.line(q), p.cmp(q)))
-                        .collect::<HashSet<_>>()
-                        .len(),
-                )
-            })
+            .cloned()
+            .zip(self.visible_counts())
             .collect()
     }
 
     pub fn destroyed_from(&self, point: &Point) -> Vec<Point> {
-        // We partition the items into groups based on their direction (line, comparison) from the
-        // point and then iterate over them in polar coordinate order one at a time.
-        let v: Vec<_> = self
-            .points
-            .iter()
-            .filter(|q| *q != point)
-            .map(|q| ((point.line(q), point.cmp(q)), point.polar_to(q), *q))
-            .collect();
-
-        let mut thetagrouped: BTreeMap<_, Vec<(PolarCoordinates, Point)>> = BTreeMap::new();
-        for (_, polar, point) in &v {
-            let atheta = ComparableFloat(polar.adjusted_theta());
-            if let Some(v) = thetagrouped.get_mut(&atheta) {
-                v.push((*polar, *point));
-            } else {
-                thetagrouped.insert(atheta, vec![(*polar, *point)]);
-            }
+        // We group the points by their direction from the station, nearest last, and then sweep
+        // the directions clockwise, destroying the nearest remaining point in each.
+        let mut rays: BTreeMap<Direction, Vec<(i64, Point)>> = BTreeMap::new();
+        for q in self.points.iter().filter(|q| *q != point) {
+            rays.entry(point.direction_to(q))
+                .or_default()
+                .push((point.distance_sq(q), *q));
         }
-        for v in thetagrouped.values_mut() {
-            v.sort();
+        for v in rays.values_mut() {
+            v.sort_by(|a, b| b.cmp(a));
         }
-        let mut seen = HashSet::new();
-        let mut r = Vec::new();
 
-        // This isn't especially efficient, but n is small.
-        while r.len() < v.len() {
-            for seq in thetagrouped.values() {
-                if let Some(point) = seq
-                    .iter()
-                    .sorted()
-                    .map(|(_, p)| p)
-                    .filter(|p| !seen.contains(p))
-                    .nth(0)
-                {
-                    seen.insert(point);
-                    r.push(*point);
-                }
-            }
+        let total = rays.values().map(Vec::len).sum();
+        let mut r = Vec::with_capacity(total);
+        while r.len() < total {
+            r.extend(rays.values_mut().filter_map(|v| v.pop()).map(|(_, p)| p));
         }
         r
     }
@@ -242,8 +157,8 @@ impl Map {
 
 #[cfg(test)]
 mod tests {
-    use super::{ComparableFloat, Line, Map, Point, PolarCoordinates};
-    use std::f64::INFINITY;
+    use super::{Direction, Map, Point};
+    use std::cmp::Ordering;
     use std::io;
     use std::io::BufRead;
 
@@ -252,16 +167,7 @@ mod tests {
     }
 
     fn best_point(s: &str) -> (Point, usize) {
-        map(s)
-            .visible_from()
-            .iter()
-            .fold((Point(0, 0), 0), |(p, pcount), (&q, &qcount)| {
-                if qcount > pcount {
-                    (q, qcount)
-                } else {
-                    (p, pcount)
-                }
-            })
+        map(s).best_station().unwrap()
     }
 
     fn destroyed_from(s: &str, p: Point) -> Vec<Point> {
@@ -269,75 +175,55 @@ mod tests {
     }
 
     #[test]
-    fn lines() {
+    fn directions() {
         assert_eq!(
-            Point(0, 0).line(&Point(1, 1)),
-            Line {
-                m: 1.0,
-                b: 0.0,
-                x: None
-            }
+            Point(0, 0).direction_to(&Point(4, 6)),
+            Direction { dx: 2, dy: 3 }
         );
         assert_eq!(
-            Point(0, 1).line(&Point(1, 3)),
-            Line {
-                m: 2.0,
-                b: 1.0,
-                x: None
-            }
+            Point(5, 5).direction_to(&Point(5, 0)),
+            Direction { dx: 0, dy: -1 }
         );
         assert_eq!(
-            Point(1, 10).line(&Point(3, 17)),
-            Line {
-                m: 3.5,
-                b: 6.5,
-                x: None
-            }
+            Point(1, 10).direction_to(&Point(3, 17)),
+            Point(3, 17).direction_to(&Point(5, 24))
         );
-        // The specific infinities don't matter here because they won't be compared.
-        assert_eq!(
-            Point(1, 10).line(&Point(1, 20)),
-            Line {
-                m: INFINITY,
-                b: INFINITY,
-                x: Some(1)
-            }
+        assert_ne!(
+            Point(1, 10).direction_to(&Point(3, 17)),
+            Point(3, 17).direction_to(&Point(1, 10))
+        );
+        // Directions which floats can't tell apart.
+        assert_ne!(
+            Direction::new(1_000_000_000, 999_999_999),
+            Direction::new(999_999_999, 999_999_998)
         );
     }
 
     #[test]
-    fn polar() {
-        let pi = 4.0 * 1.0f64.atan();
-
-        assert_eq!(
-            ComparableFloat(
-                PolarCoordinates {
-                    dsq: 1,
-                    theta: 3.0 * pi / 2.0
-                }
-                .adjusted_theta()
-            ),
-            ComparableFloat(pi)
-        );
-
-        assert_eq!(
-            ComparableFloat(Point(0, 0).polar_to(&Point(0, 1)).theta),
-            ComparableFloat(3.0 * pi / 2.0)
-        );
-
+    fn clockwise() {
+        let sweep: Vec<_> = [
+            (0, -1),
+            (1, -3),
+            (1, -1),
+            (1, 0),
+            (3, 1),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+            (-1, -1),
+            (-1, -1_000_000),
+        ]
+        .iter()
+        .map(|&(dx, dy)| Direction::new(dx, dy))
+        .collect();
+        let mut sorted = sweep.clone();
+        sorted.reverse();
+        sorted.sort();
+        assert_eq!(sorted, sweep);
         assert_eq!(
-            ComparableFloat(Point(0, 1).polar_to(&Point(0, 0)).theta),
-            ComparableFloat(pi / 2.0)
-        );
-
-        assert_eq!(
-            ComparableFloat(Point(0, 0).polar_to(&Point(1, 0)).theta),
-            ComparableFloat(0.0)
-        );
-
-        assert_eq!(
-            ComparableFloat(Point(1, 0).polar_to(&Point(0, 0)).theta),
-            ComparableFloat(pi)
+            Direction::new(2, -2).cmp(&Direction::new(1, -1)),
+            Ordering::Equal
         );
     }
 
@@ -0,0 +1,157 @@
+use super::{Direction, Map, Point};
+use std::thread;
+
+// The directions already seen from one station, as a grid of every offset the station could see
+// another point at.  Each station stamps the directions it sees with its own generation, so the
+// grid never needs clearing.
+struct Seen {
+    stamps: Vec<u32>,
+    width: i64,
+    generation: u32,
+}
+
+impl Seen {
+    fn new(width: usize, height: usize) -> Self {
+        Seen {
+            stamps: vec![0; (2 * width + 1) * (height + 1)],
+            width: width as i64,
+            generation: 0,
+        }
+    }
+
+    fn next(&mut self) {
+        self.generation += 1;
+    }
+
+    // Records `d`, which points down or right, and returns whether it's new for this station.
+    fn insert(&mut self, d: Direction) -> bool {
+        let i = (d.dy * (2 * self.width + 1) + d.dx + self.width) as usize;
+        let new = self.stamps[i] != self.generation;
+        self.stamps[i] = self.generation;
+        new
+    }
+}
+
+impl Map {
+    /// The number of other points visible from each point, in the order the points were read.
+    ///
+    /// Visibility is symmetric, so each station only looks at the points after it in reading
+    /// order and credits both ends of each pair that can see each other.  Those points all lie
+    /// below or to the right of it, and meet each ray from it nearest first, so the first point
+    /// seen in each direction is the visible one.  The stations are shared between the map's
+    /// threads.
+    pub fn visible_counts(&self) -> Vec<usize> {
+        let n = self.points.len();
+        let (width, height) = self
+            .points
+            .iter()
+            .fold((0, 0), |(w, h), p| (w.max(p.0 + 1), h.max(p.1 + 1)));
+        let scan = |first: usize, step: usize| {
+            let mut counts = vec![0; n];
+            let mut seen = Seen::new(width, height);
+            for a in (first..n).step_by(step) {
+                seen.next();
+                let p = self.points[a];
+                for (b, q) in self.points.iter().enumerate().skip(a + 1) {
+                    if seen.insert(p.direction_to(q)) {
+                        counts[a] += 1;
+                        counts[b] += 1;
+                    }
+                }
+            }
+            counts
+        };
+        if self.threads == 1 || n < 2 {
+            return scan(0, 1);
+        }
+        // Earlier stations have more points after them, so the threads take turns rather than
+        // taking a contiguous block each.
+        let threads = self.threads.min(n);
+        let scan = &scan;
+        thread::scope(|s| {
+            let handles: Vec<_> = (0..threads)
+                .map(|t| s.spawn(move || scan(t, threads)))
+                .collect();
+            handles.into_iter().fold(vec![0; n], |mut total, h| {
+                for (t, c) in total.iter_mut().zip(h.join().unwrap()) {
+                    *t += c;
+                }
+                total
+            })
+        })
+    }
+
+    /// The point from which the most other points are visible, and how many it can see.
+    ///
+    /// If several points tie, this is the first of them in reading order.
+    pub fn best_station(&self) -> Option<(Point, usize)> {
+        self.points
+            .iter()
+            .zip(self.visible_counts())
+            .fold(None, |best, (&p, count)| match best {
+                Some((_, c)) if c >= count => best,
+                _ => Some((p, count)),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Map, Point};
+    use std::collections::HashSet;
+
+    // A pseudo-random map with roughly `percent` of its cells filled.
+    fn generate(width: usize, height: usize, percent: u64, seed: u64) -> Map {
+        let mut state = seed;
+        let lines = (0..height).map(|_| {
+            (0..width)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6_364_136_223_846_793_005)
+                        .wrapping_add(1_442_695_040_888_963_407);
+                    if (state >> 33) % 100 < percent {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>()
+        });
+        Map::new(lines.collect::<Vec<_>>().into_iter())
+    }
+
+    fn naive(map: &Map) -> Vec<usize> {
+        map.points
+            .iter()
+            .map(|p| {
+                map.points
+                    .iter()
+                    .filter(|q| *q != p)
+                    .map(|q| p.direction_to(q))
+                    .collect::<HashSet<_>>()
+                    .len()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts() {
+        for &(seed, percent) in &[(1, 5), (2, 30), (3, 90), (4, 100)] {
+            let expected = naive(&generate(23, 17, percent, seed));
+            for &threads in &[1, 2, 3, 1000] {
+                let map = generate(23, 17, percent, seed).with_threads(threads);
+                assert_eq!(map.visible_counts(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn best_station() {
+        assert_eq!(generate(5, 5, 0, 1).best_station(), None);
+        let map = Map::new(vec!["..#".to_string()].into_iter());
+        assert_eq!(map.best_station(), Some((Point(2, 0), 0)));
+        // Every point on a line but the two ends sees two others; the first of those wins.
+        let map = Map::new(vec!["#.###".to_string()].into_iter());
+        assert_eq!(map.best_station(), Some((Point(2, 0), 2)));
+    }
+}
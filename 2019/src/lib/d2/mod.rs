@@ -4,6 +4,7 @@ use std::fmt;
 use std::io;
 use std::ops::{Index, IndexMut};
 use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(Copy, Clone, Debug)]
 enum Parameter {
@@ -24,9 +25,13 @@ enum Instruction {
     AdjustRelative(Parameter),
 }
 
+/// An Intcode program and its memory.
+///
+/// The memory is shared with the image the program was created from, and with any clones, until
+/// the program first writes to it, so starting many copies of one program is cheap.
 #[derive(Clone)]
 pub struct Program {
-    data: Vec<i64>,
+    data: Arc<Vec<i64>>,
     off: usize,
     base: isize,
 }
@@ -46,20 +51,31 @@ impl fmt::Display for Error {
     }
 }
 
-impl Into<io::Error> for Error {
-    fn into(self) -> io::Error {
-        io::Error::new(io::ErrorKind::InvalidData, self)
+impl From<Error> for io::Error {
+    fn from(val: Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, val)
     }
 }
 
 pub struct Iter<'a> {
     prog: &'a mut Program,
     iter: Rc<RefCell<dyn Iterator<Item = i64>>>,
+    resumable: bool,
+    waiting: bool,
 }
 
 impl<'a> Iter<'a> {
-    fn new(prog: &'a mut Program, iter: Rc<RefCell<dyn Iterator<Item = i64>>>) -> Self {
-        Iter { prog, iter }
+    fn new(
+        prog: &'a mut Program,
+        iter: Rc<RefCell<dyn Iterator<Item = i64>>>,
+        resumable: bool,
+    ) -> Self {
+        Iter {
+            prog,
+            iter,
+            resumable,
+            waiting: false,
+        }
     }
 }
 
@@ -67,6 +83,9 @@ impl<'a> Iterator for Iter<'a> {
     type Item = Result<i64, Error>;
 
     fn next(&mut self) -> Option<Result<i64, Error>> {
+        if self.waiting {
+            return None;
+        }
         while let Some(op) = self.prog.next() {
             match op {
                 Instruction::Add(a, b, s) => {
@@ -81,6 +100,12 @@ impl<'a> Iterator for Iter<'a> {
                         s,
                         match iter.next() {
                             Some(x) => x,
+                            None if self.resumable => {
+                                // Leave the program at this instruction so it can be resumed.
+                                self.prog.off -= 2;
+                                self.waiting = true;
+                                return Some(Err(Error::OutOfData));
+                            }
                             None => return Some(Err(Error::OutOfData)),
                         },
                     );
@@ -122,16 +147,27 @@ impl<'a> Iterator for Iter<'a> {
 }
 
 impl Program {
-    pub fn new(data: Vec<i64>) -> Self {
+    pub fn new<D: Into<Arc<Vec<i64>>>>(data: D) -> Self {
         Program {
-            data,
+            data: data.into(),
             off: 0,
             base: 0,
         }
     }
 
+    /// Runs the program, yielding each value it outputs.
+    ///
+    /// If the program needs input when `iter` has none left, this yields `Error::OutOfData` and
+    /// skips that instruction.
     pub fn run<'a>(&'a mut self, iter: Rc<RefCell<dyn Iterator<Item = i64>>>) -> Iter<'a> {
-        Iter::new(self, iter)
+        Iter::new(self, iter, false)
+    }
+
+    /// Runs the program like `run`, except that if it needs input when `iter` has none left, this
+    /// yields `Error::OutOfData` and ends, leaving the program waiting at that instruction, so
+    /// resuming it once more input is available carries on where it stopped.
+    pub fn resume<'a>(&'a mut self, iter: Rc<RefCell<dyn Iterator<Item = i64>>>) -> Iter<'a> {
+        Iter::new(self, iter, true)
     }
 
     fn load(&self, p: Parameter) -> i64 {
@@ -152,10 +188,11 @@ impl Program {
             Parameter::Position(x) => x,
             Parameter::Immediate(_) => return,
         };
-        if idx >= self.data.len() {
-            self.data.resize_with(idx + 1, Default::default);
+        let data = Arc::make_mut(&mut self.data);
+        if idx >= data.len() {
+            data.resize_with(idx + 1, Default::default);
         }
-        self.data[idx] = val;
+        data[idx] = val;
     }
 
     fn decode_param(&self, mode: i64, off: usize) -> Parameter {
@@ -253,7 +290,7 @@ impl Index<usize> for Program {
 
 impl IndexMut<usize> for Program {
     fn index_mut(&mut self, idx: usize) -> &mut i64 {
-        self.data.index_mut(idx)
+        Arc::make_mut(&mut self.data).index_mut(idx)
     }
 }
 
@@ -262,7 +299,9 @@ impl IntoIterator for Program {
     type IntoIter = ::std::vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.data.into_iter()
+        Arc::try_unwrap(self.data)
+            .unwrap_or_else(|data| (*data).clone())
+            .into_iter()
     }
 }
 
@@ -270,9 +309,7 @@ pub struct Parser {}
 
 impl Parser {
     pub fn parse(data: &str) -> Vec<i64> {
-        data.split(',')
-            .map(|s| i64::from_str_radix(s, 10).unwrap())
-            .collect()
+        data.split(',').map(|s| s.parse::<i64>().unwrap()).collect()
     }
 }
 
@@ -417,4 +454,27 @@ mod tests {
             vec![3]
         );
     }
+
+    #[test]
+    fn out_of_data() {
+        // Outputs its first instruction after reading over it.
+        let mut p = Program::new(Parser::parse("3,0,4,0,99"));
+        let r: Vec<_> = p.run(Rc::new(RefCell::new(vec![].into_iter()))).collect();
+        assert!(matches!(r[..], [Err(Error::OutOfData), Ok(3)]));
+    }
+
+    #[test]
+    fn resume() {
+        // Outputs the sum of two inputs.
+        let mut p = Program::new(Parser::parse("3,11,3,12,1,11,12,13,4,13,99,0,0,0"));
+        let r: Vec<_> = p
+            .resume(Rc::new(RefCell::new(vec![2].into_iter())))
+            .collect();
+        assert!(matches!(r[..], [Err(Error::OutOfData)]));
+        let r = p
+            .resume(Rc::new(RefCell::new(vec![3].into_iter())))
+            .collect::<Result<Vec<_>, Error>>()
+            .unwrap();
+        assert_eq!(r, vec![5]);
+    }
 }
@@ -1,41 +1,55 @@
 use std::cmp;
 use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::io;
 use std::ops::{Index, IndexMut};
 
+mod render;
+mod sweep;
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
 pub struct Point(pub i64, pub i64);
 
+// Each cell counts the distinct wires which visit it.  Since the wires are walked one after the
+// other, remembering the last wire to visit is enough to avoid counting a wire twice.
+#[derive(Copy, Clone, Default)]
+struct Cell {
+    wires: u32,
+    last: u32,
+}
+
 #[derive(Clone)]
 struct Row {
-    v: Vec<u8>,
+    v: Vec<Cell>,
     off: isize,
 }
 
 impl Row {
     fn new(size: usize) -> Self {
         Row {
-            v: vec![0u8; size * 2 + 1],
+            v: vec![Cell::default(); size * 2 + 1],
             off: size as isize,
         }
     }
 }
 
 impl Index<isize> for Row {
-    type Output = u8;
+    type Output = Cell;
 
-    fn index(&self, idx: isize) -> &u8 {
+    fn index(&self, idx: isize) -> &Cell {
         self.v.index((idx + self.off) as usize)
     }
 }
 
 impl IndexMut<isize> for Row {
-    fn index_mut(&mut self, idx: isize) -> &mut u8 {
+    fn index_mut(&mut self, idx: isize) -> &mut Cell {
         self.v.index_mut((idx + self.off) as usize)
     }
 }
 
 impl IntoIterator for Row {
-    type Item = u8;
+    type Item = Cell;
     type IntoIter = ::std::vec::IntoIter<Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
@@ -80,51 +94,151 @@ impl IntoIterator for Grid {
     }
 }
 
+/// The strategy used to find the points at which the wires cross.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Engine {
+    /// Mark every visited point in a dense grid centred on the origin.
+    Grid,
+    /// Intersect the segments of the paths directly with a sweep line.
+    Segments,
+}
+
+impl Engine {
+    // The grid is quadratic in the largest coordinate, so it's only worth using while it stays
+    // comfortably small.
+    const GRID_LIMIT: usize = 1 << 22;
+
+    pub fn for_paths(data: &[Vec<Segment>]) -> Self {
+        let size = Graph::extent(data) * 2 + 1;
+        match size.checked_mul(size) {
+            Some(cells) if cells <= Self::GRID_LIMIT => Engine::Grid,
+            _ => Engine::Segments,
+        }
+    }
+}
+
+/// A point visited by several wires.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Crossing {
+    pub point: Point,
+    // Each visit is a wire and the number of steps it took to get here, in the order of the wires.
+    visits: Vec<(usize, usize)>,
+}
+
+impl Crossing {
+    /// The wires which cross at this point, in ascending order.
+    pub fn wires(&self) -> Vec<usize> {
+        self.steps().into_iter().map(|(wire, _)| wire).collect()
+    }
+
+    /// Each wire which crosses at this point along with the number of steps it takes to first get
+    /// here, which is its signal delay.
+    pub fn steps(&self) -> Vec<(usize, usize)> {
+        let mut v: Vec<(usize, usize)> = Vec::new();
+        for &(wire, steps) in &self.visits {
+            if v.last().map(|&(w, _)| w) != Some(wire) {
+                v.push((wire, steps));
+            }
+        }
+        v
+    }
+
+    /// The combined signal delay of all the wires crossing here.
+    pub fn total_steps(&self) -> usize {
+        self.steps().iter().map(|&(_, steps)| steps).sum()
+    }
+
+    pub fn distance(&self) -> i64 {
+        self.point.0.abs() + self.point.1.abs()
+    }
+}
+
+impl fmt::Display for Crossing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{},{}: distance {}, total delay {}",
+            self.point.0,
+            self.point.1,
+            self.distance(),
+            self.total_steps()
+        )?;
+        for (wire, steps) in self.steps() {
+            write!(f, ", wire {} delay {}", wire, steps)?;
+        }
+        Ok(())
+    }
+}
+
 pub struct Graph {
     paths: Vec<Vec<Segment>>,
-    arr: Grid,
-    intersections: BTreeMap<Point, Vec<usize>>,
+    engine: Engine,
+    intersections: BTreeMap<Point, Vec<(usize, usize)>>,
 }
 
 impl Graph {
     pub fn new(data: Vec<Vec<Segment>>) -> Self {
-        let max_dimension: i64 = data
-            .iter()
+        let engine = Engine::for_paths(&data);
+        Self::with_engine(data, engine)
+    }
+
+    pub fn with_engine(data: Vec<Vec<Segment>>, engine: Engine) -> Self {
+        Graph {
+            paths: data,
+            engine,
+            intersections: BTreeMap::new(),
+        }
+    }
+
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    // The largest distance from the origin along either axis that any path reaches.
+    fn extent(data: &[Vec<Segment>]) -> usize {
+        data.iter()
             .map(|v| {
                 v.iter()
                     .fold((Point(0, 0), 0), |(p, m), s| {
                         let q = s.point_from(p);
-                        (q, cmp::max(m, cmp::max(q.0, q.1)))
+                        (q, cmp::max(m, cmp::max(q.0.abs(), q.1.abs())))
                     })
                     .1
             })
             .max()
-            .unwrap();
-        let max_dimension = max_dimension as usize;
-        if data.len() > 8 {
-            panic!("can't deal with more than 8 paths");
-        }
-        Graph {
-            paths: data,
-            arr: Grid::new(max_dimension as usize),
-            intersections: BTreeMap::new(),
-        }
+            .unwrap_or(0) as usize
     }
 
+    /// Finds the points crossed by every wire.
     pub fn run(&mut self) {
-        let arr = &mut self.arr;
-        let intersections = &mut self.intersections;
+        self.run_with_threshold(self.paths.len());
+    }
+
+    /// Finds the points crossed by at least `k` different wires.
+    pub fn run_with_threshold(&mut self, k: usize) {
+        let k = cmp::max(k, 1);
+        self.intersections = match self.engine {
+            Engine::Grid => self.run_grid(k),
+            Engine::Segments => sweep::crossings(&self.paths, k),
+        };
+    }
+
+    fn run_grid(&self, k: usize) -> BTreeMap<Point, Vec<(usize, usize)>> {
+        let mut arr = Grid::new(Self::extent(&self.paths));
+        let mut intersections = BTreeMap::new();
         let paths = &self.paths;
-        let len = self.paths.len();
 
         // This algorithm is two pass because it's much, much faster that way.  Storing the number
         // of steps taken for point uses so much memory that allocation dominates the time spent.
-        Self::each_point(paths, |p, i, _| Self::mark(arr, intersections, len, i, p));
-        Self::each_point(paths, |p, _, steps| {
+        Self::each_point(paths, |p, i, _| {
+            Self::mark(&mut arr, &mut intersections, k, i, p)
+        });
+        Self::each_point(paths, |p, i, steps| {
             if let Some(v) = intersections.get_mut(&p) {
-                v.push(steps)
+                v.push((i, steps))
             }
         });
+        intersections
     }
 
     fn each_point<F: FnMut(Point, usize, usize)>(paths: &[Vec<Segment>], mut f: F) {
@@ -141,22 +255,61 @@ impl Graph {
         }
     }
 
+    /// The points crossed by every wire, with the total number of steps taken to reach them.
     pub fn intersections<'a>(&'a self) -> impl Iterator<Item = (Point, usize)> + 'a {
-        self.intersections.iter().map(|(&p, v)| (p, v.iter().sum()))
+        self.crossings()
+            .filter(move |c| c.wires().len() == self.paths.len())
+            .map(|c| (c.point, c.total_steps()))
+    }
+
+    /// The crossing of every wire nearest the origin by Manhattan distance, with that distance.
+    pub fn closest_by_distance(&self) -> Option<(Point, i64)> {
+        self.intersections()
+            .map(|(p, _)| (p, p.0.abs() + p.1.abs()))
+            .filter(|&(_, d)| d > 0)
+            .min_by_key(|&(_, d)| d)
+    }
+
+    /// The crossing of every wire which takes the fewest steps in total to reach, with that
+    /// number of steps.
+    pub fn closest_by_steps(&self) -> Option<(Point, usize)> {
+        self.intersections()
+            .filter(|&(_, steps)| steps > 0)
+            .min_by_key(|&(_, steps)| steps)
+    }
+
+    /// The points which wire `i` visits more than once, with the number of steps taken at each
+    /// visit.
+    ///
+    /// This always intersects the segments directly, whichever engine is in use.
+    pub fn self_crossings(&self, i: usize) -> Vec<(Point, Vec<usize>)> {
+        sweep::self_crossings(&self.paths[i]).into_iter().collect()
+    }
+
+    /// The points found by the last run, including those crossed by only some of the wires.
+    pub fn crossings<'a>(&'a self) -> impl Iterator<Item = Crossing> + 'a {
+        self.intersections.iter().map(|(&point, v)| Crossing {
+            point,
+            visits: v.clone(),
+        })
     }
 
     fn mark(
         arr: &mut Grid,
-        intersections: &mut BTreeMap<Point, Vec<usize>>,
-        npaths: usize,
+        intersections: &mut BTreeMap<Point, Vec<(usize, usize)>>,
+        k: usize,
         i: usize,
         p: Point,
     ) {
         let (x, y) = (p.0 as isize, p.1 as isize);
-        let mask = ((1u16 << npaths) - 1) as u8;
-        arr[x][y] |= 1u8 << i;
-        if arr[x][y] == mask {
-            intersections.insert(p, vec![]);
+        let cell = &mut arr[x][y];
+        let id = i as u32 + 1;
+        if cell.last != id {
+            cell.last = id;
+            cell.wires += 1;
+            if cell.wires as usize == k {
+                intersections.insert(p, vec![]);
+            }
         }
     }
 }
@@ -167,6 +320,26 @@ pub enum Direction {
     South,
     East,
     West,
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
+impl Direction {
+    // The change in each coordinate for a single step in this direction.
+    fn unit(self) -> (i64, i64) {
+        match self {
+            Direction::North => (0, 1),
+            Direction::South => (0, -1),
+            Direction::East => (1, 0),
+            Direction::West => (-1, 0),
+            Direction::NorthEast => (1, 1),
+            Direction::NorthWest => (-1, 1),
+            Direction::SouthEast => (1, -1),
+            Direction::SouthWest => (-1, -1),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -181,54 +354,105 @@ impl Segment {
         Point(p.0 + x, p.1 + y)
     }
 
-    fn points_from(&self, p: Point) -> Box<dyn Iterator<Item = Point>> {
-        let len = self.len;
-        match self.dir {
-            Direction::North => Box::new((p.1 + 1..=(p.1 + len)).map(move |i| Point(p.0, i))),
-            Direction::South => Box::new(((p.1 - len)..p.1).rev().map(move |i| Point(p.0, i))),
-            Direction::East => Box::new((p.0 + 1..=(p.0 + len)).map(move |i| Point(i, p.1))),
-            Direction::West => Box::new(((p.0 - len)..p.0).rev().map(move |i| Point(i, p.1))),
-        }
+    fn points_from(&self, p: Point) -> impl Iterator<Item = Point> {
+        let (x, y) = self.dir.unit();
+        (1..=self.len).map(move |i| Point(p.0 + x * i, p.1 + y * i))
     }
 
     fn magnitude(&self) -> (i64, i64) {
-        match self.dir {
-            Direction::North => (0, self.len),
-            Direction::South => (0, -self.len),
-            Direction::East => (self.len, 0),
-            Direction::West => (-self.len, 0),
+        let (x, y) = self.dir.unit();
+        (x * self.len, y * self.len)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Error {
+    ExpectedDirection(usize),
+    InvalidLength(usize),
+    UnexpectedCharacter(usize),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::ExpectedDirection(off) => write!(f, "Expected a direction at offset {}", off),
+            Error::InvalidLength(off) => write!(f, "Invalid length at offset {}", off),
+            Error::UnexpectedCharacter(off) => write!(f, "Unexpected character at offset {}", off),
         }
     }
 }
 
+impl From<Error> for io::Error {
+    fn from(val: Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, val)
+    }
+}
+
 pub struct Parser {}
 
 impl Parser {
-    pub fn parse(data: &str) -> Vec<Segment> {
-        data.split(',').map(|s| Self::parse_one(s)).collect()
+    // The two-letter diagonals come first so that they're matched in full.
+    const DIRECTIONS: [(&'static str, Direction); 8] = [
+        ("NE", Direction::NorthEast),
+        ("NW", Direction::NorthWest),
+        ("SE", Direction::SouthEast),
+        ("SW", Direction::SouthWest),
+        ("R", Direction::East),
+        ("D", Direction::South),
+        ("L", Direction::West),
+        ("U", Direction::North),
+    ];
+
+    /// Parses a comma-separated path such as `R8, U5, NE3`.
+    ///
+    /// Whitespace is allowed around each direction and length.  Errors carry the byte offset into
+    /// `data` at which the problem was found.
+    pub fn parse(data: &str) -> Result<Vec<Segment>, Error> {
+        let mut off = 0;
+        let mut v = Vec::new();
+        for seg in data.split(',') {
+            v.push(Self::parse_one(seg, off)?);
+            off += seg.len() + 1;
+        }
+        Ok(v)
     }
 
-    fn parse_one(seg: &str) -> Segment {
-        let dir = match &seg[0..1] {
-            "R" => Direction::East,
-            "D" => Direction::South,
-            "L" => Direction::West,
-            "U" => Direction::North,
-            _ => panic!("not a direction"),
-        };
-        Segment {
-            dir,
-            len: i64::from_str_radix(&seg[1..], 10).unwrap(),
+    fn parse_one(seg: &str, off: usize) -> Result<Segment, Error> {
+        let skip = |s: &str, pos: usize| pos + s[pos..].len() - s[pos..].trim_start().len();
+
+        let pos = skip(seg, 0);
+        let (name, dir) = Self::DIRECTIONS
+            .iter()
+            .find(|(name, _)| seg[pos..].starts_with(name))
+            .ok_or(Error::ExpectedDirection(off + pos))?;
+
+        let pos = skip(seg, pos + name.len());
+        let digits = seg[pos..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(seg.len() - pos);
+        let len = seg[pos..pos + digits]
+            .parse::<i64>()
+            .map_err(|_| Error::InvalidLength(off + pos))?;
+
+        let pos = skip(seg, pos + digits);
+        if pos != seg.len() {
+            return Err(Error::UnexpectedCharacter(off + pos));
         }
+        Ok(Segment { dir: *dir, len })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Graph, Parser};
+    use super::{Engine, Error, Graph, Parser, Point};
 
     fn distance(path1: &str, path2: &str) -> i64 {
-        let mut g = Graph::new(vec![Parser::parse(path1), Parser::parse(path2)]);
+        let mut g = Graph::new(vec![
+            Parser::parse(path1).unwrap(),
+            Parser::parse(path2).unwrap(),
+        ]);
         g.run();
         g.intersections()
             .map(|(p, _)| p.0.abs() + p.1.abs())
@@ -238,7 +462,10 @@ mod tests {
     }
 
     fn steps(path1: &str, path2: &str) -> usize {
-        let mut g = Graph::new(vec![Parser::parse(path1), Parser::parse(path2)]);
+        let mut g = Graph::new(vec![
+            Parser::parse(path1).unwrap(),
+            Parser::parse(path2).unwrap(),
+        ]);
         g.run();
         g.intersections()
             .map(|(_, steps)| steps)
@@ -247,6 +474,219 @@ mod tests {
             .unwrap()
     }
 
+    fn intersections(paths: &[&str], engine: Engine) -> Vec<(Point, usize)> {
+        let mut g = Graph::with_engine(
+            paths.iter().map(|p| Parser::parse(p).unwrap()).collect(),
+            engine,
+        );
+        g.run();
+        g.intersections().collect()
+    }
+
+    #[test]
+    fn engines_agree() {
+        let cases: &[&[&str]] = &[
+            &["R8,U5,L5,D3", "U7,R6,D4,L4"],
+            &[
+                "R75,D30,R83,U83,L12,D49,R71,U7,L72",
+                "U62,R66,U55,R34,D71,R55,D58,R83",
+            ],
+            &[
+                "R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51",
+                "U98,R91,D20,R16,D67,R40,U7,R15,U6,R7",
+            ],
+            // Overlapping collinear segments, a wire crossing itself, and a path back to the origin.
+            &["R10,U2,L4,D4,R2,U2", "U1,R12,L3,D1,L9"],
+            &["L5,D5,R5,U5", "D5,L5,U5,R5", "L3,D10"],
+            &["R3,U3"],
+        ];
+        for paths in cases {
+            assert_eq!(
+                intersections(paths, Engine::Grid),
+                intersections(paths, Engine::Segments)
+            );
+        }
+    }
+
+    #[test]
+    fn engine_choice() {
+        assert_eq!(
+            Graph::new(vec![
+                Parser::parse("R8,U5,L5,D3").unwrap(),
+                Parser::parse("U7,R6,D4,L4").unwrap()
+            ])
+            .engine(),
+            Engine::Grid
+        );
+
+        // Mostly negative and far too large for a grid.
+        let paths = ["L2000000,D5,R1999990,U10", "D2000000,L1999995,U2000010"];
+        let mut g = Graph::new(paths.iter().map(|p| Parser::parse(p).unwrap()).collect());
+        assert_eq!(g.engine(), Engine::Segments);
+        g.run();
+        assert_eq!(
+            g.intersections().collect::<Vec<_>>(),
+            vec![
+                (Point(-1999995, -5), 2000010 + 5999990),
+                (Point(-1999995, 0), 1999995 + 5999995)
+            ]
+        );
+    }
+
+    #[test]
+    fn many_wires() {
+        let paths: Vec<_> = (1..=10).map(|i| format!("R{},U5,L{}", i, i + 3)).collect();
+        for &engine in &[Engine::Grid, Engine::Segments] {
+            let mut g = Graph::with_engine(
+                paths.iter().map(|p| Parser::parse(p).unwrap()).collect(),
+                engine,
+            );
+            g.run();
+            assert_eq!(
+                g.intersections().map(|(p, _)| p).collect::<Vec<_>>(),
+                vec![
+                    Point(-3, 5),
+                    Point(-2, 5),
+                    Point(-1, 5),
+                    Point(0, 5),
+                    Point(1, 0),
+                    Point(1, 5)
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn k_way() {
+        let paths = ["R8,U5,L5,D3", "U7,R6,D4,L4", "U2,R8"];
+        for &engine in &[Engine::Grid, Engine::Segments] {
+            let mut g = Graph::with_engine(
+                paths.iter().map(|p| Parser::parse(p).unwrap()).collect(),
+                engine,
+            );
+            g.run_with_threshold(2);
+            assert_eq!(
+                g.crossings()
+                    .map(|c| (c.point, c.steps()))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (Point(0, 1), vec![(1, 1), (2, 1)]),
+                    (Point(0, 2), vec![(1, 2), (2, 2)]),
+                    (Point(3, 2), vec![(0, 21), (2, 5)]),
+                    (Point(3, 3), vec![(0, 20), (1, 20)]),
+                    (Point(6, 5), vec![(0, 15), (1, 15)]),
+                    (Point(8, 2), vec![(0, 10), (2, 10)]),
+                ]
+            );
+            // No point is crossed by all three wires.
+            assert_eq!(g.intersections().count(), 0);
+
+            g.run();
+            assert_eq!(g.crossings().count(), 0);
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let path = Parser::parse(" NE3 ,U 2,SW10,\tL4 ").unwrap();
+        assert_eq!(
+            path[0].points_from(Point(0, 0)).collect::<Vec<_>>(),
+            vec![Point(1, 1), Point(2, 2), Point(3, 3)]
+        );
+        assert_eq!(path[2].point_from(Point(3, 5)), Point(-7, -5));
+        assert_eq!(path.len(), 4);
+
+        assert_eq!(
+            Parser::parse("R8,X5").err(),
+            Some(Error::ExpectedDirection(3))
+        );
+        assert_eq!(
+            Parser::parse("R8,,U5").err(),
+            Some(Error::ExpectedDirection(3))
+        );
+        assert_eq!(
+            Parser::parse("R8, N5").err(),
+            Some(Error::ExpectedDirection(4))
+        );
+        assert_eq!(Parser::parse("R8,U").err(), Some(Error::InvalidLength(4)));
+        assert_eq!(Parser::parse("R-8").err(), Some(Error::InvalidLength(1)));
+        assert_eq!(
+            Parser::parse("R99999999999999999999").err(),
+            Some(Error::InvalidLength(1))
+        );
+        assert_eq!(
+            Parser::parse("R8,U5 x").err(),
+            Some(Error::UnexpectedCharacter(6))
+        );
+    }
+
+    #[test]
+    fn diagonals() {
+        let cases: &[&[&str]] = &[
+            // Diagonals crossing axis-aligned segments, each other, and running along each other.
+            &["NE5,SE5,R2", "U3,R8,SW4,NW2"],
+            &["NE6,L2,SE2", "R1,NW1,NE5,R3"],
+            // These two cross between points, which doesn't count.
+            &["NE3", "R1,NW3"],
+            &["NE4,SW2,SE3", "R4,NW4", "U2,R2,D1"],
+        ];
+        for paths in cases {
+            for &k in &[1, 2, paths.len()] {
+                let crossings: Vec<_> = [Engine::Grid, Engine::Segments]
+                    .iter()
+                    .map(|&engine| {
+                        let mut g = Graph::with_engine(
+                            paths.iter().map(|p| Parser::parse(p).unwrap()).collect(),
+                            engine,
+                        );
+                        g.run_with_threshold(k);
+                        g.crossings().collect::<Vec<_>>()
+                    })
+                    .collect();
+                assert_eq!(crossings[0], crossings[1]);
+            }
+        }
+        assert_eq!(intersections(&["NE3", "R1,NW3"], Engine::Segments), vec![]);
+        assert_eq!(
+            intersections(&["NE5,SE5,R2", "U3,R8,SW4,NW2"], Engine::Segments),
+            vec![(Point(3, 3), 3 + 6), (Point(7, 3), 7 + 10)]
+        );
+    }
+
+    #[test]
+    fn delays() {
+        // The first wire loops back over the only crossing, which shouldn't add to its delay.
+        let paths = ["R10,U5,L5,D10", "D2,R3,NE3"];
+        for &engine in &[Engine::Grid, Engine::Segments] {
+            let mut g = Graph::with_engine(
+                paths.iter().map(|p| Parser::parse(p).unwrap()).collect(),
+                engine,
+            );
+            g.run();
+            let crossings: Vec<_> = g.crossings().collect();
+            assert_eq!(crossings.len(), 1);
+            assert_eq!(crossings[0].steps(), vec![(0, 5), (1, 7)]);
+            assert_eq!(crossings[0].total_steps(), 12);
+            assert_eq!(
+                crossings[0].to_string(),
+                "5,0: distance 5, total delay 12, wire 0 delay 5, wire 1 delay 7"
+            );
+            assert_eq!(g.closest_by_steps(), Some((Point(5, 0), 12)));
+            assert_eq!(g.self_crossings(0), vec![(Point(5, 0), vec![5, 25])]);
+            assert_eq!(g.self_crossings(1), vec![]);
+        }
+
+        let g = Graph::new(vec![Parser::parse("R5,L3,NW1,SE1").unwrap()]);
+        assert_eq!(
+            g.self_crossings(0),
+            vec![
+                (Point(2, 0), vec![2, 8, 10]),
+                (Point(3, 0), vec![3, 7]),
+                (Point(4, 0), vec![4, 6])
+            ]
+        );
+    }
+
     #[test]
     fn integration_distance() {
         assert_eq!(distance("R8,U5,L5,D3", "U7,R6,D4,L4"), 6);
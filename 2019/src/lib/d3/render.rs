@@ -0,0 +1,331 @@
+use super::{Graph, Point};
+use std::cmp;
+use std::io::{self, Write};
+
+// Colours for the wires, used in turn.  Red, gold, and black are kept back for the markers.
+const PALETTE: [(u8, u8, u8); 8] = [
+    (31, 119, 180),
+    (255, 127, 14),
+    (44, 160, 44),
+    (148, 103, 189),
+    (140, 86, 75),
+    (227, 119, 194),
+    (127, 127, 127),
+    (23, 190, 207),
+];
+
+const WHITE: (u8, u8, u8) = (255, 255, 255);
+const BLACK: (u8, u8, u8) = (0, 0, 0);
+const DISTANCE: (u8, u8, u8) = (214, 39, 40);
+const STEPS: (u8, u8, u8) = (230, 180, 0);
+
+fn colour(i: usize) -> (u8, u8, u8) {
+    PALETTE[i % PALETTE.len()]
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// A raster image for the PPM output.
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<(u8, u8, u8)>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Canvas {
+            width,
+            height,
+            pixels: vec![WHITE; width * height],
+        }
+    }
+
+    fn set(&mut self, x: i64, y: i64, c: (u8, u8, u8)) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            self.pixels[y as usize * self.width + x as usize] = c;
+        }
+    }
+
+    fn line(&mut self, a: (i64, i64), b: (i64, i64), c: (u8, u8, u8)) {
+        // Bresenham's algorithm, which handles lines in any direction.
+        let (dx, dy) = ((b.0 - a.0).abs(), -(b.1 - a.1).abs());
+        let (sx, sy) = ((b.0 - a.0).signum(), (b.1 - a.1).signum());
+        let (mut x, mut y) = a;
+        let mut err = dx + dy;
+        loop {
+            self.set(x, y, c);
+            if (x, y) == b {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y += sy;
+            }
+        }
+    }
+
+    fn square(&mut self, p: (i64, i64), r: i64, c: (u8, u8, u8)) {
+        for y in (p.1 - r)..=(p.1 + r) {
+            for x in (p.0 - r)..=(p.0 + r) {
+                self.set(x, y, c);
+            }
+        }
+    }
+
+    fn outline(&mut self, p: (i64, i64), r: i64, c: (u8, u8, u8)) {
+        let (x0, y0, x1, y1) = (p.0 - r, p.1 - r, p.0 + r, p.1 + r);
+        self.line((x0, y0), (x1, y0), c);
+        self.line((x1, y0), (x1, y1), c);
+        self.line((x1, y1), (x0, y1), c);
+        self.line((x0, y1), (x0, y0), c);
+    }
+}
+
+impl Graph {
+    // The corners of each path, starting at the origin.
+    fn vertices(&self) -> Vec<Vec<Point>> {
+        self.paths
+            .iter()
+            .map(|path| {
+                let mut v = vec![Point(0, 0)];
+                for segment in path {
+                    v.push(segment.point_from(*v.last().unwrap()));
+                }
+                v
+            })
+            .collect()
+    }
+
+    // The smallest and largest coordinates reached by any path, always including the origin.
+    fn bounds(vertices: &[Vec<Point>]) -> (Point, Point) {
+        vertices
+            .iter()
+            .flatten()
+            .fold((Point(0, 0), Point(0, 0)), |(lo, hi), p| {
+                (
+                    Point(cmp::min(lo.0, p.0), cmp::min(lo.1, p.1)),
+                    Point(cmp::max(hi.0, p.0), cmp::max(hi.1, p.1)),
+                )
+            })
+    }
+
+    /// Writes the paths as an SVG image, with north at the top.
+    ///
+    /// Each wire gets its own colour.  Points crossed by every wire are marked in black, the
+    /// closest by Manhattan distance is ringed in red, the closest by steps in gold, and the
+    /// origin is labelled.
+    pub fn write_svg<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let vertices = self.vertices();
+        let (lo, hi) = Self::bounds(&vertices);
+        let span = cmp::max(cmp::max(hi.0 - lo.0, hi.1 - lo.1), 1) as f64;
+        // A unit for the size of markers and text which is in proportion to the drawing.
+        let u = span / 100.0;
+        let margin = 5.0 * u;
+        let (width, height) = ((hi.0 - lo.0) as f64, (hi.1 - lo.1) as f64);
+
+        writeln!(
+            w,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+            lo.0 as f64 - margin,
+            -hi.1 as f64 - margin,
+            width + 2.0 * margin,
+            height + 2.0 * margin
+        )?;
+        writeln!(
+            w,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"white\"/>",
+            lo.0 as f64 - margin,
+            -hi.1 as f64 - margin,
+            width + 2.0 * margin,
+            height + 2.0 * margin
+        )?;
+        for (i, path) in vertices.iter().enumerate() {
+            let points = path
+                .iter()
+                .map(|p| format!("{},{}", p.0, -p.1))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(
+                w,
+                "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\" vector-effect=\"non-scaling-stroke\"><title>wire {}</title></polyline>",
+                points,
+                hex(colour(i)),
+                i
+            )?;
+        }
+        for (p, _) in self.intersections() {
+            writeln!(
+                w,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>",
+                p.0,
+                -p.1,
+                u / 2.0,
+                hex(BLACK)
+            )?;
+        }
+        let highlights = [
+            self.closest_by_distance()
+                .map(|(p, d)| (p, format!("distance {}", d), DISTANCE)),
+            self.closest_by_steps()
+                .map(|(p, s)| (p, format!("steps {}", s), STEPS)),
+        ];
+        for (i, (p, label, c)) in highlights.iter().flatten().enumerate() {
+            writeln!(
+                w,
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"3\" vector-effect=\"non-scaling-stroke\"/>",
+                p.0,
+                -p.1,
+                (2 + i) as f64 * u,
+                hex(*c)
+            )?;
+            writeln!(
+                w,
+                "<text x=\"{}\" y=\"{}\" font-size=\"{}\" fill=\"{}\">{}</text>",
+                p.0 as f64 + 3.0 * u,
+                -p.1 as f64 - (2 + 3 * i) as f64 * u,
+                3.0 * u,
+                hex(*c),
+                label
+            )?;
+        }
+        writeln!(
+            w,
+            "<circle cx=\"0\" cy=\"0\" r=\"{}\" fill=\"{}\"/>",
+            u,
+            hex(BLACK)
+        )?;
+        writeln!(
+            w,
+            "<text x=\"{}\" y=\"{}\" font-size=\"{}\">origin</text>",
+            1.5 * u,
+            3.5 * u,
+            3.0 * u
+        )?;
+        writeln!(w, "</svg>")
+    }
+
+    /// Writes the paths as a plain (ASCII) PPM image no larger than `size` pixels on either side.
+    ///
+    /// The colours are as for `write_svg`.  Since there's no text, the origin is drawn as a black
+    /// outline.
+    pub fn write_ppm<W: Write>(&self, w: &mut W, size: usize) -> io::Result<()> {
+        const MARGIN: i64 = 6;
+        if size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "PPM images must be at least one pixel wide",
+            ));
+        }
+        let vertices = self.vertices();
+        let (lo, hi) = Self::bounds(&vertices);
+        let span = cmp::max(cmp::max(hi.0 - lo.0, hi.1 - lo.1), 1);
+        // Small images get a narrower margin, so they still fit in `size`.
+        let margin = cmp::min(MARGIN, (size as i64 - 1) / 2);
+        let inner = size as i64 - 2 * margin;
+        let scale = |v: i64| (v as f64 * (inner - 1) as f64 / span as f64).round() as i64;
+        let pixel = |p: Point| (margin + scale(p.0 - lo.0), margin + scale(hi.1 - p.1));
+
+        let mut canvas = Canvas::new(
+            (scale(hi.0 - lo.0) + 2 * margin + 1) as usize,
+            (scale(hi.1 - lo.1) + 2 * margin + 1) as usize,
+        );
+        for (i, path) in vertices.iter().enumerate() {
+            for pair in path.windows(2) {
+                canvas.line(pixel(pair[0]), pixel(pair[1]), colour(i));
+            }
+        }
+        for (p, _) in self.intersections() {
+            canvas.square(pixel(p), 1, BLACK);
+        }
+        if let Some((p, _)) = self.closest_by_distance() {
+            canvas.outline(pixel(p), 3, DISTANCE);
+        }
+        if let Some((p, _)) = self.closest_by_steps() {
+            canvas.outline(pixel(p), 5, STEPS);
+        }
+        canvas.outline(pixel(Point(0, 0)), 2, BLACK);
+
+        writeln!(w, "P3")?;
+        writeln!(w, "{} {}", canvas.width, canvas.height)?;
+        writeln!(w, "255")?;
+        // Plain PPM lines are meant to be short, so write one pixel per line.
+        for (r, g, b) in canvas.pixels {
+            writeln!(w, "{} {} {}", r, g, b)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Graph, Parser};
+
+    fn graph() -> Graph {
+        let mut g = Graph::new(vec![
+            Parser::parse("R8,U5,L5,D3").unwrap(),
+            Parser::parse("U7,R6,D4,L4").unwrap(),
+        ]);
+        g.run();
+        g
+    }
+
+    #[test]
+    fn svg() {
+        let mut v = Vec::new();
+        graph().write_svg(&mut v).unwrap();
+        let s = String::from_utf8(v).unwrap();
+        assert!(s.starts_with("<svg "));
+        assert!(s.trim_end().ends_with("</svg>"));
+        assert_eq!(s.matches("<polyline ").count(), 2);
+        assert!(s.contains("points=\"0,0 8,0 8,-5 3,-5 3,-2\""));
+        assert!(s.contains(">distance 6</text>"));
+        assert!(s.contains(">steps 30</text>"));
+        assert!(s.contains(">origin</text>"));
+    }
+
+    #[test]
+    fn ppm() {
+        let mut v = Vec::new();
+        graph().write_ppm(&mut v, 100).unwrap();
+        let s = String::from_utf8(v).unwrap();
+        let lines: Vec<_> = s.lines().collect();
+        assert_eq!(lines[0], "P3");
+        assert_eq!(lines[1], "100 89");
+        assert_eq!(lines[2], "255");
+        assert_eq!(lines.len(), 3 + 100 * 89);
+        // The crossing at (3, 3) is marked in black.
+        assert_eq!(lines[3 + (6 + 44) * 100 + 6 + 33], "0 0 0");
+    }
+
+    #[test]
+    fn ppm_small() {
+        for size in 1..20 {
+            let mut v = Vec::new();
+            graph().write_ppm(&mut v, size).unwrap();
+            let s = String::from_utf8(v).unwrap();
+            let dims: Vec<usize> = s
+                .lines()
+                .nth(1)
+                .unwrap()
+                .split(' ')
+                .map(|d| d.parse().unwrap())
+                .collect();
+            assert!(
+                dims[0] <= size && dims[1] <= size,
+                "{:?} for {}",
+                dims,
+                size
+            );
+            assert_eq!(s.lines().count(), 3 + dims[0] * dims[1]);
+        }
+        assert!(graph().write_ppm(&mut Vec::new(), 0).is_err());
+    }
+}
@@ -0,0 +1,320 @@
+use super::{Point, Segment};
+use std::cmp;
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter;
+
+/// The four kinds of line a segment can lie on.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+enum Orientation {
+    Horizontal,
+    Vertical,
+    // Running from south-west to north-east.
+    Diagonal,
+    // Running from north-west to south-east.
+    AntiDiagonal,
+}
+
+impl Orientation {
+    const ALL: [Orientation; 4] = [
+        Orientation::Horizontal,
+        Orientation::Vertical,
+        Orientation::Diagonal,
+        Orientation::AntiDiagonal,
+    ];
+
+    fn of(unit: (i64, i64)) -> Self {
+        match unit {
+            (_, 0) => Orientation::Horizontal,
+            (0, _) => Orientation::Vertical,
+            (x, y) if x == y => Orientation::Diagonal,
+            _ => Orientation::AntiDiagonal,
+        }
+    }
+
+    // The coefficients (a, b) such that a * x + b * y is the same for every point on a line.
+    fn form(self) -> (i64, i64) {
+        match self {
+            Orientation::Horizontal => (0, 1),
+            Orientation::Vertical => (1, 0),
+            Orientation::Diagonal => (1, -1),
+            Orientation::AntiDiagonal => (1, 1),
+        }
+    }
+
+    /// The value identifying the line of this orientation through `p`.
+    fn line(self, p: Point) -> i64 {
+        let (a, b) = self.form();
+        a * p.0 + b * p.1
+    }
+
+    /// The coordinate which varies along a line of this orientation.
+    fn param(self, p: Point) -> i64 {
+        match self {
+            Orientation::Vertical => p.1,
+            _ => p.0,
+        }
+    }
+
+    fn point(self, line: i64, k: i64) -> Point {
+        match self {
+            Orientation::Horizontal => Point(k, line),
+            Orientation::Vertical => Point(line, k),
+            Orientation::Diagonal => Point(k, k - line),
+            Orientation::AntiDiagonal => Point(k, line - k),
+        }
+    }
+
+    /// The point lying on both line `m` of orientation `self` and line `n` of orientation `other`,
+    /// if it has integer coordinates.
+    fn meet(self, m: i64, other: Orientation, n: i64) -> Option<Point> {
+        let ((a, b), (c, d)) = (self.form(), other.form());
+        let det = a * d - b * c;
+        let (x, y) = (m * d - b * n, a * n - m * c);
+        if det == 0 || x % det != 0 || y % det != 0 {
+            None
+        } else {
+            Some(Point(x / det, y / det))
+        }
+    }
+}
+
+/// A segment placed at its absolute position along a path.
+///
+/// Like walking the segment, a span covers the points after `start` up to and including its end,
+/// and `steps` is the number of steps the wire has taken when it reaches `start`.
+struct Span {
+    wire: usize,
+    start: Point,
+    unit: (i64, i64),
+    len: i64,
+    steps: usize,
+}
+
+impl Span {
+    fn at(&self, d: i64) -> Point {
+        Point(
+            self.start.0 + self.unit.0 * d,
+            self.start.1 + self.unit.1 * d,
+        )
+    }
+
+    fn orientation(&self) -> Orientation {
+        Orientation::of(self.unit)
+    }
+
+    fn line(&self) -> i64 {
+        self.orientation().line(self.start)
+    }
+
+    // The inclusive range that `f` takes over the span, including the start.
+    fn range<F: Fn(Point) -> i64>(&self, f: F) -> (i64, i64) {
+        let (a, b) = (f(self.start), f(self.at(self.len)));
+        (cmp::min(a, b), cmp::max(a, b))
+    }
+
+    fn bounds(&self) -> (i64, i64) {
+        let o = self.orientation();
+        self.range(|p| o.param(p))
+    }
+
+    fn point_on_line(&self, k: i64) -> Point {
+        self.orientation().point(self.line(), k)
+    }
+
+    /// The number of steps from the start of the span to `p`, if the span visits `p`.
+    fn offset(&self, p: Point) -> Option<i64> {
+        let d = if self.unit.0 != 0 {
+            (p.0 - self.start.0) * self.unit.0
+        } else {
+            (p.1 - self.start.1) * self.unit.1
+        };
+        if d >= 1 && d <= self.len && self.at(d) == p {
+            Some(d)
+        } else {
+            None
+        }
+    }
+}
+
+// Events are ordered so that a crossing span sees the spans entering or leaving at the same place.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+enum Event {
+    Enter,
+    Cross,
+    Leave,
+}
+
+fn spans<'a, I: Iterator<Item = &'a [Segment]>>(paths: I) -> Vec<Span> {
+    let mut v = Vec::new();
+    for (wire, path) in paths.enumerate() {
+        let mut cur = Point(0, 0);
+        let mut steps = 0;
+        for segment in path {
+            if segment.len > 0 {
+                v.push(Span {
+                    wire,
+                    start: cur,
+                    unit: segment.dir.unit(),
+                    len: segment.len,
+                    steps,
+                });
+            }
+            cur = segment.point_from(cur);
+            steps += segment.len as usize;
+        }
+    }
+    v
+}
+
+// Whether two spans should be checked against one another.
+type Pairing = fn(&Span, &Span) -> bool;
+
+/// Finds the points where a span of orientation `p` crosses a span of orientation `q`, for spans
+/// which `pair` accepts.
+///
+/// Measured by the lines of `q` it passes through, each span of orientation `p` covers an interval
+/// on a single line of `p`, while each span of orientation `q` sits on a single line of `q`.
+/// Sweeping over the lines of `q` then turns this into the usual problem of intersecting
+/// horizontal and vertical segments.
+fn perpendicular(
+    spans: &[Span],
+    p: Orientation,
+    q: Orientation,
+    pair: Pairing,
+    set: &mut BTreeSet<Point>,
+) {
+    let mut events = Vec::new();
+    for (i, s) in spans.iter().enumerate() {
+        if s.orientation() == p {
+            let (lo, hi) = s.range(|x| q.line(x));
+            events.push((lo, Event::Enter, i));
+            events.push((hi, Event::Leave, i));
+        } else if s.orientation() == q {
+            events.push((s.line(), Event::Cross, i));
+        }
+    }
+    events.sort_unstable();
+
+    let mut active: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+    for (n, event, i) in events {
+        let s = &spans[i];
+        match event {
+            Event::Enter => active.entry(s.line()).or_default().push(i),
+            Event::Leave => {
+                if let Some(v) = active.get_mut(&s.line()) {
+                    v.retain(|&j| j != i);
+                    if v.is_empty() {
+                        active.remove(&s.line());
+                    }
+                }
+            }
+            Event::Cross => {
+                let (lo, hi) = s.range(|x| p.line(x));
+                for (&m, v) in active.range(lo..=hi) {
+                    if v.iter().any(|&j| pair(&spans[j], s)) {
+                        // Diagonal lines may meet between points, where no wire can visit.
+                        if let Some(x) = p.meet(m, q, n) {
+                            set.insert(x);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Finds the points shared by collinear spans which run along one another, for spans which `pair`
+/// accepts.
+fn overlaps<'a, I: Iterator<Item = &'a Span>>(spans: I, pair: Pairing, set: &mut BTreeSet<Point>) {
+    let mut lines: BTreeMap<i64, Vec<&Span>> = BTreeMap::new();
+    for s in spans {
+        lines.entry(s.line()).or_default().push(s);
+    }
+    for mut v in lines.into_values() {
+        v.sort_by_key(|s| s.bounds());
+        let mut active: Vec<&Span> = Vec::new();
+        for s in v {
+            let (lo, hi) = s.bounds();
+            active.retain(|a| a.bounds().1 >= lo);
+            for a in active.iter().filter(|a| pair(a, s)) {
+                for k in lo..=cmp::min(hi, a.bounds().1) {
+                    set.insert(s.point_on_line(k));
+                }
+            }
+            active.push(s);
+        }
+    }
+}
+
+// Finds the points where any two spans accepted by `pair` meet.
+fn candidates(spans: &[Span], pair: Pairing) -> BTreeSet<Point> {
+    let mut set = BTreeSet::new();
+    for (i, &p) in Orientation::ALL.iter().enumerate() {
+        for &q in &Orientation::ALL[i + 1..] {
+            perpendicular(spans, p, q, pair, &mut set);
+        }
+        overlaps(
+            spans.iter().filter(|s| s.orientation() == p),
+            pair,
+            &mut set,
+        );
+    }
+    set
+}
+
+/// Finds every visit each wire makes to each candidate, in the order the wires make them.
+fn visits(spans: &[Span], candidates: &BTreeSet<Point>) -> BTreeMap<Point, Vec<(usize, usize)>> {
+    let index: BTreeSet<_> = Orientation::ALL
+        .iter()
+        .flat_map(|&o| candidates.iter().map(move |&p| (o, o.line(p), o.param(p))))
+        .collect();
+    let mut visits: BTreeMap<Point, Vec<(usize, usize)>> = BTreeMap::new();
+    for s in spans {
+        let (o, line) = (s.orientation(), s.line());
+        let (lo, hi) = s.bounds();
+        for &(_, _, k) in index.range((o, line, lo)..=(o, line, hi)) {
+            let p = s.point_on_line(k);
+            if let Some(d) = s.offset(p) {
+                visits
+                    .entry(p)
+                    .or_default()
+                    .push((s.wire, s.steps + d as usize));
+            }
+        }
+    }
+    visits
+}
+
+/// Finds the points crossed by at least `k` wires, like the grid does, but in time and space
+/// proportional to the number of segments and crossings rather than to the area the paths cover.
+pub(super) fn crossings(paths: &[Vec<Segment>], k: usize) -> BTreeMap<Point, Vec<(usize, usize)>> {
+    let spans = spans(paths.iter().map(|p| p.as_slice()));
+    let candidates = if k <= 1 {
+        // Every point a wire visits is crossed by at least one wire.
+        spans
+            .iter()
+            .flat_map(|s| (1..=s.len).map(move |d| s.at(d)))
+            .collect()
+    } else {
+        candidates(&spans, |a, b| a.wire != b.wire)
+    };
+
+    visits(&spans, &candidates)
+        .into_iter()
+        .filter(|(_, v)| {
+            let wires: BTreeSet<_> = v.iter().map(|&(wire, _)| wire).collect();
+            wires.len() >= k
+        })
+        .collect()
+}
+
+/// Finds the points which a single path visits more than once, with the steps taken at each visit.
+pub(super) fn self_crossings(path: &[Segment]) -> BTreeMap<Point, Vec<usize>> {
+    let spans = spans(iter::once(path));
+    let candidates = candidates(&spans, |a, b| a.wire == b.wire);
+    visits(&spans, &candidates)
+        .into_iter()
+        .filter(|(_, v)| v.len() > 1)
+        .map(|(p, v)| (p, v.into_iter().map(|(_, steps)| steps).collect()))
+        .collect()
+}
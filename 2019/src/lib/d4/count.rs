@@ -0,0 +1,220 @@
+use super::{digits, Policy, Rule};
+use std::cmp;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// What we need to remember about the digits chosen so far to check the rest of a number.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+struct State {
+    last: Option<u8>,
+    // The length of the current run of identical digits, capped at one more than any rule needs.
+    run: usize,
+    // The run rules which an earlier, finished run has already satisfied.
+    satisfied: u64,
+}
+
+/// The rules of a policy in a form suited to building numbers one digit at a time.
+pub(super) struct Counter<'a> {
+    policy: &'a Policy,
+    runs: Vec<&'a Rule>,
+    cap: usize,
+    // Keyed by the final length, since the length rules depend on it.
+    memo: HashMap<(usize, usize, State), usize>,
+}
+
+impl<'a> Counter<'a> {
+    pub(super) fn new(policy: &'a Policy) -> Self {
+        let runs: Vec<_> = policy
+            .rules
+            .iter()
+            .filter(|r| matches!(r, Rule::RunOfExactly(_) | Rule::RunOfAtLeast(_)))
+            .collect();
+        assert!(runs.len() <= 64, "too many run rules to count");
+        let cap = runs
+            .iter()
+            .map(|r| match r {
+                Rule::RunOfExactly(k) | Rule::RunOfAtLeast(k) => *k + 1,
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(1);
+        Counter {
+            policy,
+            runs,
+            cap,
+            memo: HashMap::new(),
+        }
+    }
+
+    fn start() -> State {
+        State {
+            last: None,
+            run: 0,
+            satisfied: 0,
+        }
+    }
+
+    // Marks the run rules satisfied by a finished run of length `run`.
+    fn close(&self, run: usize, satisfied: u64) -> u64 {
+        self.runs
+            .iter()
+            .enumerate()
+            .fold(satisfied, |acc, (i, r)| match r {
+                Rule::RunOfExactly(k) if run == *k => acc | (1 << i),
+                Rule::RunOfAtLeast(k) if run >= *k => acc | (1 << i),
+                _ => acc,
+            })
+    }
+
+    /// The state after appending `d`, or `None` if no number continuing this way can be valid.
+    fn push(&self, s: State, d: u8) -> Option<State> {
+        for rule in &self.policy.rules {
+            match rule {
+                Rule::Forbidden(v) if v.contains(&d) => return None,
+                Rule::Monotonic(order) => match s.last {
+                    Some(last) if !order.allows(last, d) => return None,
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+        Some(if s.last == Some(d) {
+            State {
+                run: cmp::min(s.run + 1, self.cap),
+                ..s
+            }
+        } else {
+            State {
+                last: Some(d),
+                run: 1,
+                satisfied: match s.last {
+                    Some(_) => self.close(s.run, s.satisfied),
+                    None => s.satisfied,
+                },
+            }
+        })
+    }
+
+    fn accepts(&self, s: State, len: usize) -> bool {
+        let all = if self.runs.len() == 64 {
+            !0
+        } else {
+            (1u64 << self.runs.len()) - 1
+        };
+        self.close(s.run, s.satisfied) == all
+            && self.policy.rules.iter().all(|r| match r {
+                Rule::Length(n) => *n == len,
+                _ => true,
+            })
+    }
+
+    /// The number of ways to finish a number of `len` digits with `left` digits still to choose.
+    fn free(&mut self, s: State, left: usize, len: usize) -> usize {
+        if left == 0 {
+            return self.accepts(s, len) as usize;
+        }
+        if let Some(&n) = self.memo.get(&(len, left, s)) {
+            return n;
+        }
+        let mut n = 0;
+        for d in 0..self.policy.base {
+            if let Some(t) = self.push(s, d) {
+                n += self.free(t, left - 1, len);
+            }
+        }
+        self.memo.insert((len, left, s), n);
+        n
+    }
+
+    /// The number of valid numbers of exactly `len` digits which are less than `limit`, given as
+    /// its digits, or all of them if `limit` is `None`.
+    fn with_length(&mut self, len: usize, limit: Option<&[u8]>) -> usize {
+        let mut count = 0;
+        let mut prefix = Some(Self::start());
+        for i in 0..len {
+            let s = match prefix {
+                Some(s) => s,
+                None => break,
+            };
+            // Only zero itself starts with a zero.
+            let lowest = if i == 0 && len > 1 { 1 } else { 0 };
+            let top = limit.map(|l| l[i]).unwrap_or(self.policy.base);
+            for d in lowest..top {
+                if let Some(t) = self.push(s, d) {
+                    count += self.free(t, len - i - 1, len);
+                }
+            }
+            prefix = match limit {
+                Some(l) => self.push(s, l[i]),
+                None => None,
+            };
+        }
+        count
+    }
+
+    /// The number of valid numbers less than `n`.
+    pub(super) fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        let limit = digits(n, self.policy.base);
+        let shorter: usize = (1..limit.len())
+            .map(|len| self.with_length(len, None))
+            .sum();
+        shorter + self.with_length(limit.len(), Some(&limit))
+    }
+
+    /// The valid number with `rank` valid numbers below it, given that it is less than `end`.
+    pub(super) fn select(&mut self, rank: usize, end: usize) -> usize {
+        let base = self.policy.base;
+        let limit = digits(end, base);
+        let mut rank = rank;
+        let mut len = 1;
+        // Numbers as long as `end` aren't all counted, since the longest could overflow.
+        while len < limit.len() {
+            let n = self.with_length(len, None);
+            if rank < n {
+                break;
+            }
+            rank -= n;
+            len += 1;
+        }
+        let mut s = Self::start();
+        let mut n = 0;
+        for i in 0..len {
+            let lowest = if i == 0 && len > 1 { 1 } else { 0 };
+            for d in lowest..base {
+                let t = match self.push(s, d) {
+                    Some(t) => t,
+                    None => continue,
+                };
+                let ways = self.free(t, len - i - 1, len);
+                if rank < ways {
+                    s = t;
+                    n = n * base as usize + d as usize;
+                    break;
+                }
+                rank -= ways;
+            }
+        }
+        n
+    }
+}
+
+impl Policy {
+    /// The number of values in `range` which satisfy the policy.
+    ///
+    /// This works a digit at a time rather than testing each value, so it's quick even for huge
+    /// ranges.  Custom predicates can only be checked against whole numbers, though, so a policy
+    /// with any falls back to testing each value which satisfies the other rules.
+    pub fn count(&self, range: Range<usize>) -> usize {
+        if range.start >= range.end {
+            return 0;
+        }
+        if self.rules.iter().any(|r| matches!(r, Rule::Custom(_))) {
+            return self.candidates(range).count();
+        }
+        let mut c = Counter::new(self);
+        c.below(range.end) - c.below(range.start)
+    }
+}
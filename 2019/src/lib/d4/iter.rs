@@ -0,0 +1,102 @@
+use super::count::Counter;
+use super::{digits, Policy, Rule};
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+/// The numbers in a range which satisfy a policy, in increasing order.
+///
+/// Rather than testing every number in turn, this ranks the candidates which satisfy the rules
+/// other than custom predicates, and builds each one directly from its rank a digit at a time.
+/// Custom predicates are then checked against each candidate.
+pub struct Candidates<'a> {
+    policy: &'a Policy,
+    counter: Counter<'a>,
+    end: usize,
+    // The ranks of the candidates not yet yielded from either end.
+    front: usize,
+    back: usize,
+    custom: bool,
+}
+
+impl<'a> Candidates<'a> {
+    fn new(policy: &'a Policy, range: Range<usize>) -> Self {
+        let mut counter = Counter::new(policy);
+        let (front, back) = if range.start < range.end {
+            (counter.below(range.start), counter.below(range.end))
+        } else {
+            (0, 0)
+        };
+        Candidates {
+            policy,
+            counter,
+            end: range.end,
+            front,
+            back,
+            custom: policy.rules.iter().any(|r| matches!(r, Rule::Custom(_))),
+        }
+    }
+
+    fn accepts(&self, n: usize) -> bool {
+        if !self.custom {
+            return true;
+        }
+        let digits = digits(n, self.policy.base);
+        self.policy.rules.iter().all(|r| match r {
+            Rule::Custom(_) => r.check(&digits),
+            _ => true,
+        })
+    }
+}
+
+impl<'a> Iterator for Candidates<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.front < self.back {
+            let n = self.counter.select(self.front, self.end);
+            self.front += 1;
+            if self.accepts(n) {
+                return Some(n);
+            }
+        }
+        None
+    }
+
+    fn nth(&mut self, n: usize) -> Option<usize> {
+        if self.custom {
+            for _ in 0..n {
+                self.next()?;
+            }
+        } else {
+            self.front = self.front.saturating_add(n).min(self.back);
+        }
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.back - self.front;
+        (if self.custom { 0 } else { left }, Some(left))
+    }
+}
+
+impl<'a> DoubleEndedIterator for Candidates<'a> {
+    fn next_back(&mut self) -> Option<usize> {
+        while self.front < self.back {
+            self.back -= 1;
+            let n = self.counter.select(self.back, self.end);
+            if self.accepts(n) {
+                return Some(n);
+            }
+        }
+        None
+    }
+}
+
+impl<'a> FusedIterator for Candidates<'a> {}
+
+impl Policy {
+    /// The values in `range` which satisfy the policy, found lazily in increasing order.
+    pub fn candidates(&self, range: Range<usize>) -> Candidates<'_> {
+        Candidates::new(self, range)
+    }
+}
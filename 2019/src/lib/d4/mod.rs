@@ -1,72 +1,337 @@
-use std::collections::BTreeMap;
+use std::error;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::rc::Rc;
 
-pub struct Filter {}
+mod count;
+mod iter;
 
-impl Filter {
-    pub fn validate_p1(n: usize) -> bool {
-        let s = format!("{}", n);
-        // Is this a six-digit number?
-        if s.len() != 6 {
-            return false;
+pub use self::iter::Candidates;
+
+/// The order which the digits of a number must follow, from most to least significant.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Order {
+    Increasing,
+    NonDecreasing,
+    Decreasing,
+    NonIncreasing,
+}
+
+impl Order {
+    fn allows(self, a: u8, b: u8) -> bool {
+        match self {
+            Order::Increasing => a < b,
+            Order::NonDecreasing => a <= b,
+            Order::Decreasing => a > b,
+            Order::NonIncreasing => a >= b,
         }
-        // Is there at least one consecutive pair of digits?
-        if !s
-            .chars()
-            .fold(('0', false), |(last, pair), c| (c, c == last || pair))
-            .1
-        {
-            return false;
+    }
+}
+
+type Check = dyn Fn(&[u8]) -> bool;
+
+/// A named test on the digits of a number, most significant first.
+#[derive(Clone)]
+pub struct Predicate {
+    name: String,
+    f: Rc<Check>,
+}
+
+impl Predicate {
+    pub fn new<F: Fn(&[u8]) -> bool + 'static>(name: &str, f: F) -> Self {
+        Predicate {
+            name: name.to_string(),
+            f: Rc::new(f),
         }
-        // Do the digits never decrease?
-        if !s
-            .chars()
-            .fold(('0', true), |(last, acc), c| (c, c >= last && acc))
-            .1
-        {
-            return false;
+    }
+}
+
+impl fmt::Debug for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Predicate({:?})", self.name)
+    }
+}
+
+/// A single constraint on the digits of a number.
+#[derive(Clone, Debug)]
+pub enum Rule {
+    /// The number has exactly this many digits.
+    Length(usize),
+    /// Each digit is in this order with respect to the one before it.
+    Monotonic(Order),
+    /// Some run of identical adjacent digits is exactly this long.
+    RunOfExactly(usize),
+    /// Some run of identical adjacent digits is at least this long.
+    RunOfAtLeast(usize),
+    /// None of these digits appear.
+    Forbidden(Vec<u8>),
+    /// The predicate accepts the digits, most significant first, in the policy's base.
+    Custom(Predicate),
+}
+
+impl Rule {
+    pub fn check(&self, digits: &[u8]) -> bool {
+        match self {
+            Rule::Length(n) => digits.len() == *n,
+            Rule::Monotonic(order) => digits.windows(2).all(|w| order.allows(w[0], w[1])),
+            Rule::RunOfExactly(k) => runs(digits).any(|r| r == *k),
+            Rule::RunOfAtLeast(k) => runs(digits).any(|r| r >= *k),
+            Rule::Forbidden(v) => !digits.iter().any(|d| v.contains(d)),
+            Rule::Custom(p) => (p.f)(digits),
         }
-        true
     }
+}
 
-    pub fn validate_p2(n: usize) -> bool {
-        let s = format!("{}", n);
-        // Is this a six-digit number?
-        if s.len() != 6 {
-            return false;
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Rule::Length(n) => write!(f, "has exactly {} digits", n),
+            Rule::Monotonic(Order::Increasing) => write!(f, "has digits which always increase"),
+            Rule::Monotonic(Order::NonDecreasing) => write!(f, "has digits which never decrease"),
+            Rule::Monotonic(Order::Decreasing) => write!(f, "has digits which always decrease"),
+            Rule::Monotonic(Order::NonIncreasing) => write!(f, "has digits which never increase"),
+            Rule::RunOfExactly(k) => write!(f, "has a run of exactly {} identical digits", k),
+            Rule::RunOfAtLeast(k) => write!(f, "has a run of at least {} identical digits", k),
+            Rule::Forbidden(v) => {
+                let s: Vec<_> = v.iter().map(|d| d.to_string()).collect();
+                write!(f, "has none of the digits {}", s.join(", "))
+            }
+            Rule::Custom(p) => write!(f, "{}", p.name),
         }
-        // Do the digits never decrease?
-        if !s
-            .chars()
-            .fold((' ', true), |(last, acc), c| (c, c >= last && acc))
-            .1
-        {
-            return false;
+    }
+}
+
+// The lengths of the runs of identical adjacent digits, in order.
+fn runs(digits: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    let mut i = 0;
+    std::iter::from_fn(move || {
+        let start = i;
+        let d = *digits.get(start)?;
+        while i < digits.len() && digits[i] == d {
+            i += 1;
         }
-        // Is there at least one consecutive pair of digits that isn't part of a triplet?
-        let is_pair = !s
-            .chars()
-            .fold(BTreeMap::<char, usize>::new(), |mut map, c| {
-                if let Some(v) = map.get_mut(&c) {
-                    *v += 1;
-                } else {
-                    map.insert(c, 1);
-                };
-                map
-            })
-            .iter()
-            .any(|(_, &v)| v == 2);
-        if is_pair {
-            return false;
+        Some(i - start)
+    })
+}
+
+fn digits(n: usize, base: u8) -> Vec<u8> {
+    let base = base as usize;
+    let mut v = Vec::new();
+    let mut n = n;
+    loop {
+        v.push((n % base) as u8);
+        n /= base;
+        if n == 0 {
+            break;
         }
-        true
+    }
+    v.reverse();
+    v
+}
+
+/// A set of rules which the digits of a number must satisfy, checked in order.
+#[derive(Clone, Debug)]
+pub struct Policy {
+    rules: Vec<Rule>,
+    base: u8,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            rules: Vec::new(),
+            base: 10,
+        }
+    }
+}
+
+impl Policy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks the digits of numbers written in `base` rather than in decimal.
+    pub fn in_base(mut self, base: u8) -> Self {
+        assert!(base >= 2, "base must be at least 2");
+        self.base = base;
+        self
+    }
+
+    pub fn with(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Six digits which never decrease, with at least one pair of identical adjacent digits.
+    pub fn p1() -> Self {
+        Policy::new()
+            .with(Rule::Length(6))
+            .with(Rule::RunOfAtLeast(2))
+            .with(Rule::Monotonic(Order::NonDecreasing))
+    }
+
+    /// Six digits which never decrease, with a pair of identical adjacent digits which isn't part
+    /// of a larger group.
+    pub fn p2() -> Self {
+        Policy::new()
+            .with(Rule::Length(6))
+            .with(Rule::Monotonic(Order::NonDecreasing))
+            .with(Rule::RunOfExactly(2))
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn validate(&self, n: usize) -> bool {
+        self.explain(n).is_none()
+    }
+
+    /// The first rule which `n` fails, if any.
+    pub fn explain(&self, n: usize) -> Option<&Rule> {
+        let digits = digits(n, self.base);
+        self.rules.iter().find(|r| !r.check(&digits))
+    }
+
+    /// How many values in `range` each rule is the first to reject, in the order of the rules.
+    ///
+    /// This has to test every value, so it's only suited to ranges of puzzle size.
+    pub fn rejections(&self, range: Range<usize>) -> Vec<(&Rule, usize)> {
+        let mut counts = vec![0; self.rules.len()];
+        for n in range {
+            let digits = digits(n, self.base);
+            if let Some(i) = self.rules.iter().position(|r| !r.check(&digits)) {
+                counts[i] += 1;
+            }
+        }
+        self.rules.iter().zip(counts).collect()
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    UnknownRule(usize),
+    InvalidNumber(usize),
+    ExpectedHyphen(usize),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::UnknownRule(off) => write!(f, "Unknown rule at offset {}", off),
+            Error::InvalidNumber(off) => write!(f, "Invalid number at offset {}", off),
+            Error::ExpectedHyphen(off) => write!(f, "Expected a hyphen at offset {}", off),
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(val: Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, val)
+    }
+}
+
+pub struct Parser {}
+
+impl Parser {
+    const ORDERS: [(&'static str, Order); 4] = [
+        ("increasing", Order::Increasing),
+        ("nondecreasing", Order::NonDecreasing),
+        ("decreasing", Order::Decreasing),
+        ("nonincreasing", Order::NonIncreasing),
+    ];
+
+    /// Parses a puzzle range such as `240298-784956`, which includes both ends.
+    pub fn range(data: &str) -> Result<Range<usize>, Error> {
+        let data = data.trim_end();
+        let hyphen = data.find('-').ok_or(Error::ExpectedHyphen(data.len()))?;
+        let low = Self::number(&data[..hyphen], 0)?;
+        let high = Self::number(&data[hyphen + 1..], hyphen + 1)?;
+        let end = high
+            .checked_add(1)
+            .ok_or(Error::InvalidNumber(hyphen + 1))?;
+        Ok(low..end)
+    }
+
+    /// Parses a comma-separated policy such as `length=6, nondecreasing, run=2`.
+    ///
+    /// Each item is one of `p1` or `p2` for the rules of that part of the puzzle, `length=N`, an
+    /// order (`increasing`, `nondecreasing`, `decreasing` or `nonincreasing`), `run=K` for a run of
+    /// exactly `K`, `run>=K` for a run of at least `K`, `forbid=DIGITS` for digits which mustn't
+    /// appear, or `base=B` for numbers written in base `B`.  Digits above 9 are written as letters.
+    /// Errors carry the byte offset into `data` at which the problem was found.
+    pub fn policy(data: &str) -> Result<Policy, Error> {
+        let mut off = 0;
+        let mut p = Policy::new();
+        for item in data.split(',') {
+            let pos = off + item.len() - item.trim_start().len();
+            p = Self::parse_one(p, item.trim(), pos)?;
+            off += item.len() + 1;
+        }
+        Ok(p)
+    }
+
+    fn parse_one(p: Policy, item: &str, off: usize) -> Result<Policy, Error> {
+        let value = |prefix: &str| Self::number(&item[prefix.len()..], off + prefix.len());
+        if let Some((_, order)) = Self::ORDERS.iter().find(|(name, _)| *name == item) {
+            return Ok(p.with(Rule::Monotonic(*order)));
+        }
+        match item {
+            "p1" => Ok(Policy::p1().rules.into_iter().fold(p, Policy::with)),
+            "p2" => Ok(Policy::p2().rules.into_iter().fold(p, Policy::with)),
+            _ if item.starts_with("length=") => Ok(p.with(Rule::Length(value("length=")?))),
+            _ if item.starts_with("run>=") => Ok(p.with(Rule::RunOfAtLeast(value("run>=")?))),
+            _ if item.starts_with("run=") => Ok(p.with(Rule::RunOfExactly(value("run=")?))),
+            _ if item.starts_with("base=") => match value("base=")? {
+                b @ 2..=36 => Ok(p.in_base(b as u8)),
+                _ => Err(Error::InvalidNumber(off + "base=".len())),
+            },
+            _ if item.starts_with("forbid=") => {
+                let digits = item["forbid=".len()..]
+                    .char_indices()
+                    .map(|(i, c)| {
+                        c.to_digit(36)
+                            .map(|d| d as u8)
+                            .ok_or(Error::InvalidNumber(off + "forbid=".len() + i))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(p.with(Rule::Forbidden(digits)))
+            }
+            _ => Err(Error::UnknownRule(off)),
+        }
+    }
+
+    fn number(s: &str, off: usize) -> Result<usize, Error> {
+        s.trim().parse().map_err(|_| Error::InvalidNumber(off))
+    }
+}
+
+pub struct Filter {}
+
+impl Filter {
+    pub fn validate_p1(n: usize) -> bool {
+        Policy::p1().validate(n)
+    }
+
+    pub fn validate_p2(n: usize) -> bool {
+        Policy::p2().validate(n)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Filter;
+    use super::{Filter, Order, Parser, Policy, Predicate, Rule};
+    use std::ops::Range;
+
+    fn brute_force(p: &Policy, range: Range<usize>) -> usize {
+        range.filter(|&n| p.validate(n)).count()
+    }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn validate_p1() {
         assert_eq!(Filter::validate_p1(111111), true);
         assert_eq!(Filter::validate_p1(223450), false);
@@ -75,6 +340,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn validate_p2() {
         assert_eq!(Filter::validate_p2(111111), false);
         assert_eq!(Filter::validate_p2(112233), true);
@@ -82,4 +348,194 @@ mod tests {
         assert_eq!(Filter::validate_p2(111122), true);
         assert_eq!(Filter::validate_p2(111223), true);
     }
+
+    #[test]
+    fn explain() {
+        let explain = |p: &Policy, n| p.explain(n).map(|r| r.to_string());
+        let p1 = Policy::p1();
+        assert_eq!(explain(&p1, 111111), None);
+        assert_eq!(
+            explain(&p1, 223450),
+            Some("has digits which never decrease".to_string())
+        );
+        assert_eq!(
+            explain(&p1, 123789),
+            Some("has a run of at least 2 identical digits".to_string())
+        );
+        assert_eq!(
+            explain(&p1, 12345),
+            Some("has exactly 6 digits".to_string())
+        );
+        assert_eq!(
+            explain(&Policy::p2(), 123444),
+            Some("has a run of exactly 2 identical digits".to_string())
+        );
+    }
+
+    #[test]
+    fn count() {
+        let policies = vec![
+            Policy::p1(),
+            Policy::p2(),
+            Policy::new(),
+            Policy::new()
+                .with(Rule::Monotonic(Order::Decreasing))
+                .with(Rule::Forbidden(vec![3])),
+            Policy::new()
+                .with(Rule::RunOfExactly(1))
+                .with(Rule::RunOfAtLeast(3))
+                .with(Rule::Monotonic(Order::NonIncreasing)),
+            Policy::new()
+                .with(Rule::Length(4))
+                .with(Rule::RunOfExactly(2))
+                .with(Rule::Forbidden(vec![0, 9])),
+        ];
+        let ranges = [0..100_000, 123_456..234_567, 7..8, 0..1, 1..1, 99..1001];
+        for p in &policies {
+            for r in ranges.iter().cloned() {
+                assert_eq!(p.count(r.clone()), brute_force(p, r));
+            }
+        }
+        assert_eq!(Policy::p1().count(240_298..784_956), 1150);
+        assert_eq!(Policy::p2().count(240_298..784_956), 748);
+    }
+
+    #[test]
+    fn count_huge() {
+        // With no leading zeros, digits which never decrease are all from 1 to 9, and there are
+        // C(n + 8, 8) ways of choosing n of them in order.
+        let choose = |n: usize, k: usize| (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1));
+        let p = Policy::new().with(Rule::Monotonic(Order::NonDecreasing));
+        assert_eq!(
+            p.count(1..1_000_000_000_000_000),
+            (1..=15).map(|n| choose(n + 8, 8)).sum::<usize>()
+        );
+        assert!(Policy::p2().count(0..usize::MAX) > 0);
+    }
+
+    #[test]
+    fn composed() {
+        let p = Policy::new()
+            .with(Rule::Monotonic(Order::Increasing))
+            .with(Rule::Forbidden(vec![0, 7]))
+            .with(Rule::Custom(Predicate::new("has an even digit sum", |d| {
+                d.iter().map(|&x| x as usize).sum::<usize>() % 2 == 0
+            })));
+        assert!(p.validate(13));
+        assert!(p.validate(1489));
+        assert!(!p.validate(1479));
+        assert!(!p.validate(133));
+        assert_eq!(
+            p.explain(1479).map(|r| r.to_string()),
+            Some("has none of the digits 0, 7".to_string())
+        );
+        assert_eq!(
+            p.explain(12).map(|r| r.to_string()),
+            Some("has an even digit sum".to_string())
+        );
+        assert!(Rule::Monotonic(Order::NonIncreasing).check(&[9, 9, 4, 0]));
+        assert!(!Rule::Monotonic(Order::Decreasing).check(&[9, 9, 4, 0]));
+        assert!(Rule::RunOfExactly(3).check(&[1, 1, 2, 2, 2, 1]));
+        assert!(!Rule::RunOfExactly(3).check(&[2, 2, 2, 2]));
+    }
+
+    #[test]
+    fn candidates() {
+        let even = Predicate::new("has an even digit sum", |d| {
+            d.iter().map(|&x| x as usize).sum::<usize>() % 2 == 0
+        });
+        let policies = vec![
+            Policy::p1(),
+            Policy::p2(),
+            Policy::new()
+                .with(Rule::Monotonic(Order::Decreasing))
+                .with(Rule::Forbidden(vec![3])),
+            Policy::new()
+                .with(Rule::Monotonic(Order::NonDecreasing))
+                .with(Rule::Custom(even)),
+            Policy::new().in_base(2).with(Rule::RunOfExactly(3)),
+            Policy::new()
+                .in_base(16)
+                .with(Rule::Monotonic(Order::Increasing))
+                .with(Rule::Forbidden(vec![0xa])),
+        ];
+        let ranges = [0..20_000, 123_456..134_567, 7..8, 0..1, 1..1, 99..1001];
+        for p in &policies {
+            for r in &ranges {
+                let expected: Vec<_> = r.clone().filter(|&n| p.validate(n)).collect();
+                assert_eq!(p.candidates(r.clone()).collect::<Vec<_>>(), expected);
+                let mut rev: Vec<_> = p.candidates(r.clone()).rev().collect();
+                rev.reverse();
+                assert_eq!(rev, expected);
+                for i in [0, 1, 5, 100] {
+                    assert_eq!(p.candidates(r.clone()).nth(i), expected.get(i).cloned());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn candidates_huge() {
+        let p = Policy::p2();
+        let expected: Vec<_> = (240_298..784_956).filter(|&n| p.validate(n)).collect();
+        let mut c = p.candidates(240_298..784_956);
+        assert_eq!(c.size_hint(), (748, Some(748)));
+        assert_eq!(c.next(), expected.first().cloned());
+        assert_eq!(c.next_back(), expected.last().cloned());
+        assert_eq!(c.nth(745), expected.get(746).cloned());
+        assert_eq!(c.next(), None);
+
+        let p = Policy::new().with(Rule::Monotonic(Order::NonDecreasing));
+        let mut c = p.candidates(0..usize::MAX);
+        assert_eq!(c.next_back(), Some(17_999_999_999_999_999_999));
+        assert_eq!(c.nth(10), Some(11));
+        assert_eq!(
+            Policy::new()
+                .in_base(2)
+                .candidates(0..usize::MAX)
+                .next_back(),
+            Some(usize::MAX - 1)
+        );
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Parser::range("240298-784956\n").unwrap(), 240_298..784_957);
+        assert!(Parser::range("240298").is_err());
+        assert!(Parser::range("240298-x").is_err());
+
+        let describe = |p: &Policy| p.rules().iter().map(|r| r.to_string()).collect::<Vec<_>>();
+        let p = Parser::policy("length=6, nondecreasing, run=2").unwrap();
+        assert_eq!(describe(&p), describe(&Policy::p2()));
+        assert_eq!(p.count(240_298..784_956), 748);
+        let p = Parser::policy("p1,forbid=07").unwrap();
+        assert_eq!(p.rules().len(), 4);
+        assert!(p.validate(112_345));
+        assert!(!p.validate(112_347));
+        let p = Parser::policy("base=16,increasing,run>=1").unwrap();
+        assert!(p.validate(0x12f));
+        assert!(!p.validate(0x1f2));
+        assert_eq!(
+            Parser::policy("p1, sorted").unwrap_err().to_string(),
+            "Unknown rule at offset 4"
+        );
+        assert_eq!(
+            Parser::policy("forbid=0!").unwrap_err().to_string(),
+            "Invalid number at offset 8"
+        );
+        assert!(Parser::policy("base=40").is_err());
+    }
+
+    #[test]
+    fn rejections() {
+        let p = Policy::p1();
+        let counts: Vec<_> = p
+            .rejections(240_298..784_956)
+            .into_iter()
+            .map(|(r, n)| (r.to_string(), n))
+            .collect();
+        let total: usize = counts.iter().map(|(_, n)| n).sum();
+        assert_eq!(total + 1150, 784_956 - 240_298);
+        assert_eq!(counts[0], ("has exactly 6 digits".to_string(), 0));
+    }
 }
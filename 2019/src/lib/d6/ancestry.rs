@@ -0,0 +1,80 @@
+use super::{Error, Graph, Id};
+
+/// An index of each object's ancestors for answering many queries about a graph.
+///
+/// Each object records the ancestors 1, 2, 4, 8 and so on orbits above it, so that any ancestor
+/// can be reached in a logarithmic number of jumps (binary lifting).
+pub struct Ancestry<'a> {
+    graph: &'a Graph,
+    // `up[k][i]` is the ancestor 2^k orbits above object `i`, or its root if that is nearer.
+    up: Vec<Vec<Id>>,
+}
+
+impl<'a> Ancestry<'a> {
+    fn new(graph: &'a Graph) -> Self {
+        let max = graph.depths.iter().copied().max().unwrap_or(0);
+        let mut up = vec![graph.parents.clone()];
+        while 1 << up.len() <= max {
+            let prev = up.last().unwrap();
+            let next = prev.iter().map(|&p| prev[p as usize]).collect();
+            up.push(next);
+        }
+        Ancestry { graph, up }
+    }
+
+    // The ancestor `n` orbits above object `i`.
+    fn lift(&self, mut i: usize, n: usize) -> usize {
+        for (k, level) in self.up.iter().enumerate() {
+            if n & (1 << k) != 0 {
+                i = level[i] as usize;
+            }
+        }
+        i
+    }
+
+    fn lca(&self, a: &str, b: &str) -> Result<(usize, usize, usize), Error> {
+        let g = self.graph;
+        let (a, b) = (g.id(a)?, g.id(b)?);
+        if g.systems[a] != g.systems[b] {
+            return Err(Error::Unreachable(
+                g.names[a].to_string(),
+                g.names[b].to_string(),
+            ));
+        }
+        let (da, db) = (g.depths[a], g.depths[b]);
+        let (mut x, mut y) = if da > db {
+            (self.lift(a, da - db), b)
+        } else {
+            (a, self.lift(b, db - da))
+        };
+        if x != y {
+            for level in self.up.iter().rev() {
+                if level[x] != level[y] {
+                    x = level[x] as usize;
+                    y = level[y] as usize;
+                }
+            }
+            x = self.up[0][x] as usize;
+        }
+        Ok((a, b, x))
+    }
+
+    /// As for `Graph::common_ancestor`.
+    pub fn common_ancestor(&self, a: &str, b: &str) -> Result<&'a str, Error> {
+        let (_, _, ancestor) = self.lca(a, b)?;
+        Ok(&self.graph.names[ancestor])
+    }
+
+    /// As for `Graph::distance`.
+    pub fn distance(&self, a: &str, b: &str) -> Result<usize, Error> {
+        let (a, b, ancestor) = self.lca(a, b)?;
+        let d = &self.graph.depths;
+        Ok(d[a] + d[b] - 2 * d[ancestor])
+    }
+}
+
+impl Graph {
+    pub fn ancestry(&self) -> Ancestry<'_> {
+        Ancestry::new(self)
+    }
+}
@@ -1,80 +1,362 @@
-use std::collections::{HashMap, VecDeque};
+use std::cmp;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+mod ancestry;
+mod render;
+
+pub use self::ancestry::Ancestry;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Error {
+    MissingParenthesis(usize),
+    EmptyName(usize),
+    DuplicateParent(usize),
+    Cycle(String),
+    Unrooted(String),
+    Unknown(String),
+    OrbitsNothing(String),
+    Unreachable(String, String),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::MissingParenthesis(line) => write!(f, "Missing parenthesis on line {}", line),
+            Error::EmptyName(line) => write!(f, "Empty object name on line {}", line),
+            Error::DuplicateParent(line) => write!(f, "Second parent for object on line {}", line),
+            Error::Cycle(obj) => write!(f, "Orbit cycle through {}", obj),
+            Error::Unrooted(obj) => write!(f, "Object {} does not orbit COM", obj),
+            Error::Unknown(obj) => write!(f, "Unknown object {}", obj),
+            Error::OrbitsNothing(obj) => write!(f, "Object {} orbits nothing", obj),
+            Error::Unreachable(a, b) => {
+                write!(f, "Objects {} and {} are in separate systems", a, b)
+            }
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(val: Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, val)
+    }
+}
+
+/// Statistics for one independent system of orbits.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct System {
+    /// The object at the center, which orbits nothing.
+    pub root: String,
+    /// The number of objects, including the root.
+    pub objects: usize,
+    /// The total number of direct and indirect orbits.
+    pub orbits: usize,
+    /// The largest number of orbits of any one object.
+    pub depth: usize,
+}
+
+// An interned object name, which indexes the per-object vectors of a graph.
+type Id = u32;
+
+// The parent of an object not yet known to orbit anything, while building a graph.
+const NO_PARENT: Id = Id::MAX;
 
 pub struct Graph {
-    orbits: HashMap<String, String>,
+    // Each name is allocated once and shared between the two.
+    names: Vec<Arc<str>>,
+    ids: HashMap<Arc<str>, Id>,
+    // The object each object orbits; a root is its own parent.
+    parents: Vec<Id>,
+    depths: Vec<usize>,
+    // The index into `roots` of the system each object belongs to.
+    systems: Vec<usize>,
+    roots: Vec<Id>,
 }
 
 impl Graph {
-    pub fn new(orbits: HashMap<String, String>) -> Self {
-        Graph { orbits }
+    /// Builds a graph from a map of each object to the object it orbits.
+    ///
+    /// Every object must lead back to COM; an object which doesn't, or which is part of a cycle, is
+    /// an error.
+    pub fn new(orbits: HashMap<String, String>) -> Result<Self, Error> {
+        Self::build(orbits, false)
     }
 
-    pub fn traverse(&self) -> HashMap<String, usize> {
-        let mut map = HashMap::new();
-        // The center of mass orbits nothing.
-        map.insert("COM".to_string(), 0);
+    /// Builds a graph which may hold several independent systems.
+    ///
+    /// Each object which orbits nothing is the root of its own system; depths are counted from
+    /// there.  Cycles are still an error.
+    pub fn forest(orbits: HashMap<String, String>) -> Result<Self, Error> {
+        Self::build(orbits, true)
+    }
 
-        for k in self.orbits.keys() {
-            self.count(&mut map, &k);
+    fn intern(&mut self, name: String) -> Id {
+        if let Some(&id) = self.ids.get(name.as_str()) {
+            return id;
         }
-        map
+        let id = self.names.len() as Id;
+        let name: Arc<str> = name.into();
+        self.ids.insert(Arc::clone(&name), id);
+        self.names.push(name);
+        self.parents.push(NO_PARENT);
+        id
     }
 
-    fn count(&self, m: &mut HashMap<String, usize>, s: &str) -> usize {
-        if let Some(x) = m.get(s) {
-            return *x;
+    // Finds the number of direct and indirect orbits of each object, walking up from each one
+    // until reaching an object already seen, so that long chains don't need deep recursion.
+    fn build(orbits: HashMap<String, String>, forest: bool) -> Result<Self, Error> {
+        const UNSEEN: usize = usize::MAX;
+        assert!(orbits.len() < Id::MAX as usize, "too many objects");
+        let mut g = Graph {
+            names: Vec::with_capacity(orbits.len() + 1),
+            ids: HashMap::with_capacity(orbits.len() + 1),
+            parents: Vec::with_capacity(orbits.len() + 1),
+            depths: Vec::new(),
+            systems: Vec::new(),
+            roots: Vec::new(),
+        };
+        if !forest {
+            // The center of mass orbits nothing.
+            let com = g.intern("COM".to_string());
+            g.roots.push(com);
+        }
+        for (obj, parent) in orbits {
+            let (obj, parent) = (g.intern(obj), g.intern(parent));
+            g.parents[obj as usize] = parent;
         }
-        let n = self.count(m, &self.orbits[s]);
-        m.insert(s.to_string(), n + 1);
-        m[s]
-    }
 
-    pub fn common_ancestor(&self, a: &str, b: &str) -> String {
-        let a_path = self.path(a);
-        let b_path = self.path(b);
-        let mut last = "COM";
-        for (ai, bi) in a_path.iter().zip(b_path.iter()) {
-            if ai != bi {
-                return last.to_string();
+        let n = g.names.len();
+        g.depths = vec![UNSEEN; n];
+        g.systems = vec![0; n];
+        if let Some(&com) = g.roots.first() {
+            g.depths[com as usize] = 0;
+        }
+        let mut chain: Vec<usize> = Vec::new();
+        for start in 0..n {
+            chain.clear();
+            let mut this = start;
+            let (base, system) = loop {
+                if g.depths[this] != UNSEEN {
+                    break (g.depths[this], g.systems[this]);
+                }
+                // A chain longer than the number of objects must have gone round a cycle.
+                if chain.len() > n {
+                    return Err(Error::Cycle(g.names[this].to_string()));
+                }
+                if g.parents[this] != NO_PARENT {
+                    chain.push(this);
+                    this = g.parents[this] as usize;
+                } else if forest {
+                    g.depths[this] = 0;
+                    g.systems[this] = g.roots.len();
+                    g.roots.push(this as Id);
+                } else {
+                    return Err(Error::Unrooted(g.names[this].to_string()));
+                }
+            };
+            for (i, &obj) in chain.iter().rev().enumerate() {
+                g.depths[obj] = base + i + 1;
+                g.systems[obj] = system;
             }
-            last = ai;
         }
-        last.to_string()
+        // Roots are their own parents, which keeps walks up the graph simple.  The center of
+        // mass is a root even if the map says it orbits something.
+        for &root in &g.roots {
+            g.parents[root as usize] = root;
+        }
+        Ok(g)
+    }
+
+    fn id(&self, obj: &str) -> Result<usize, Error> {
+        self.ids
+            .get(obj)
+            .map(|&id| id as usize)
+            .ok_or_else(|| Error::Unknown(obj.to_string()))
     }
 
-    fn path(&self, obj: &str) -> VecDeque<String> {
-        let mut v = VecDeque::new();
-        let mut this = obj;
-        while let Some(cur) = self.orbits.get(this) {
-            v.push_front(cur.to_string());
-            this = cur;
+    // The object `i` orbits, if any.
+    fn parent_of(&self, i: usize) -> Option<usize> {
+        match self.parents[i] as usize {
+            p if p == i => None,
+            p => Some(p),
         }
+    }
+
+    /// The objects which orbit nothing, in order of name.
+    pub fn roots(&self) -> Vec<&str> {
+        let mut v: Vec<_> = self
+            .roots
+            .iter()
+            .map(|&r| &*self.names[r as usize])
+            .collect();
+        v.sort_unstable();
         v
     }
 
+    /// Statistics for each independent system, in order of the name of the root.
+    pub fn systems(&self) -> Vec<System> {
+        let mut v: Vec<_> = self
+            .roots
+            .iter()
+            .map(|&root| System {
+                root: self.names[root as usize].to_string(),
+                objects: 0,
+                orbits: 0,
+                depth: 0,
+            })
+            .collect();
+        for (&system, &depth) in self.systems.iter().zip(&self.depths) {
+            let s = &mut v[system];
+            s.objects += 1;
+            s.orbits += depth;
+            s.depth = cmp::max(s.depth, depth);
+        }
+        v.sort_unstable_by(|a, b| a.root.cmp(&b.root));
+        v
+    }
+
+    pub fn traverse(&self) -> HashMap<String, usize> {
+        self.names
+            .iter()
+            .map(|n| n.to_string())
+            .zip(self.depths.iter().copied())
+            .collect()
+    }
+
+    /// The total number of direct and indirect orbits of every object.
+    pub fn total_orbits(&self) -> usize {
+        self.depths.iter().sum()
+    }
+
+    /// The number of direct and indirect orbits of `obj`.
+    pub fn depth(&self, obj: &str) -> Option<usize> {
+        self.id(obj).ok().map(|i| self.depths[i])
+    }
+
+    /// The object `obj` orbits directly.
+    pub fn parent(&self, obj: &str) -> Option<&str> {
+        self.lookup_parent(obj).ok().flatten()
+    }
+
+    fn lca(&self, a: usize, b: usize) -> Result<usize, Error> {
+        if self.systems[a] != self.systems[b] {
+            return Err(Error::Unreachable(
+                self.names[a].to_string(),
+                self.names[b].to_string(),
+            ));
+        }
+        let (mut a, mut b) = (a, b);
+        while self.depths[a] > self.depths[b] {
+            a = self.parents[a] as usize;
+        }
+        while self.depths[b] > self.depths[a] {
+            b = self.parents[b] as usize;
+        }
+        while a != b {
+            a = self.parents[a] as usize;
+            b = self.parents[b] as usize;
+        }
+        Ok(a)
+    }
+
+    /// The deepest object which both `a` and `b` orbit, directly or indirectly, or are.
+    ///
+    /// Objects in separate systems have no common ancestor, which is an error.
+    pub fn common_ancestor(&self, a: &str, b: &str) -> Result<&str, Error> {
+        let ancestor = self.lca(self.id(a)?, self.id(b)?)?;
+        Ok(&self.names[ancestor])
+    }
+
+    /// The number of orbits between objects `a` and `b`.
+    pub fn distance(&self, a: &str, b: &str) -> Result<usize, Error> {
+        let (a, b) = (self.id(a)?, self.id(b)?);
+        let ancestor = self.lca(a, b)?;
+        Ok(self.depths[a] + self.depths[b] - 2 * self.depths[ancestor])
+    }
+
+    /// The objects on the way from `a` to `b`, including both.
+    pub fn path_between(&self, a: &str, b: &str) -> Result<Vec<&str>, Error> {
+        let (a, b) = (self.id(a)?, self.id(b)?);
+        let ancestor = self.lca(a, b)?;
+        let up = |mut i: usize| {
+            let mut v = vec![i];
+            while i != ancestor {
+                i = self.parents[i] as usize;
+                v.push(i);
+            }
+            v
+        };
+        let mut path = up(a);
+        let mut rest = up(b);
+        rest.pop();
+        path.extend(rest.into_iter().rev());
+        Ok(path.into_iter().map(|i| &*self.names[i]).collect())
+    }
+
+    /// The number of orbital transfers needed to go from the object `a` orbits to the object `b`
+    /// orbits.
+    pub fn transfers(&self, a: &str, b: &str) -> Result<usize, Error> {
+        let parent = |obj: &str| {
+            self.lookup_parent(obj)?
+                .ok_or_else(|| Error::OrbitsNothing(obj.to_string()))
+        };
+        self.distance(parent(a)?, parent(b)?)
+    }
+
+    fn lookup_parent(&self, obj: &str) -> Result<Option<&str>, Error> {
+        Ok(self.parent_of(self.id(obj)?).map(|p| &*self.names[p]))
+    }
+
     pub fn transfers_between(&self, a: &str, b: &str) -> usize {
-        let map = self.traverse();
-        let ancestor = self.common_ancestor(a, b);
-        map[a] + map[b] - 2 * map[&ancestor]
+        self.distance(a, b).unwrap()
+    }
+
+    /// The distances between many pairs of objects at once.
+    ///
+    /// This builds an index of ancestors first, so that each query takes time logarithmic in the
+    /// depth of the objects rather than linear.
+    pub fn distances(&self, pairs: &[(&str, &str)]) -> Vec<Result<usize, Error>> {
+        let index = self.ancestry();
+        pairs.iter().map(|(a, b)| index.distance(a, b)).collect()
     }
 }
 
 pub struct Parser {}
 
 impl Parser {
-    pub fn parse(inp: &[String]) -> HashMap<String, String> {
-        inp.iter()
-            .map(|s| {
-                let v: Vec<_> = s.split(')').collect();
-                (v[1].to_string(), v[0].to_string())
-            })
-            .collect()
+    /// Parses lines of the form `A)B`, meaning that B orbits A, into a map from each object to
+    /// the object it orbits.
+    ///
+    /// Errors carry the line number, starting from 1, at which the problem was found.
+    pub fn parse(inp: &[String]) -> Result<HashMap<String, String>, Error> {
+        let mut map = HashMap::new();
+        for (i, s) in inp.iter().enumerate() {
+            let line = i + 1;
+            let mut it = s.trim().splitn(2, ')');
+            let (parent, obj) = match (it.next(), it.next()) {
+                (Some(parent), Some(obj)) => (parent, obj),
+                _ => return Err(Error::MissingParenthesis(line)),
+            };
+            if parent.is_empty() || obj.is_empty() {
+                return Err(Error::EmptyName(line));
+            }
+            match map.insert(obj.to_string(), parent.to_string()) {
+                Some(old) if old != parent => return Err(Error::DuplicateParent(line)),
+                _ => (),
+            }
+        }
+        Ok(map)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Graph, Parser};
+    use super::{Error, Graph, Parser, System};
     use std::io;
     use std::io::BufRead;
 
@@ -113,8 +395,12 @@ I)SAN"
             .lines()
             .map(|r| r.unwrap())
             .collect::<Vec<_>>();
-        let orbits = Parser::parse(&v);
-        Graph::new(orbits)
+        let orbits = Parser::parse(&v).unwrap();
+        Graph::new(orbits).unwrap()
+    }
+
+    fn lines(data: &[&str]) -> Vec<String> {
+        data.iter().map(|s| s.to_string()).collect()
     }
 
     #[test]
@@ -123,16 +409,22 @@ I)SAN"
         assert_eq!(g["D"], 3);
         assert_eq!(g["L"], 7);
         assert_eq!(g["COM"], 0);
-        assert_eq!(g.iter().map(|(_, v)| *v).sum::<usize>(), 42);
+        assert_eq!(g.values().copied().sum::<usize>(), 42);
     }
 
     #[test]
     fn common_ancestor() {
         let g = graph(data_p2());
-        assert_eq!(g.common_ancestor("YOU", "SAN"), "D");
-        assert_eq!(g.common_ancestor("K", "I"), "D");
-        assert_eq!(g.common_ancestor("YOU", "L"), "K");
-        assert_eq!(g.common_ancestor("G", "D"), "B");
+        assert_eq!(g.common_ancestor("YOU", "SAN"), Ok("D"));
+        assert_eq!(g.common_ancestor("K", "I"), Ok("D"));
+        assert_eq!(g.common_ancestor("YOU", "L"), Ok("K"));
+        assert_eq!(g.common_ancestor("G", "D"), Ok("B"));
+        assert_eq!(g.common_ancestor("B", "L"), Ok("B"));
+        assert_eq!(g.common_ancestor("COM", "COM"), Ok("COM"));
+        assert_eq!(
+            g.common_ancestor("YOU", "X"),
+            Err(Error::Unknown("X".to_string()))
+        );
     }
 
     #[test]
@@ -143,4 +435,133 @@ I)SAN"
         assert_eq!(g.transfers_between("YOU", "L"), 2);
         assert_eq!(g.transfers_between("G", "D"), 3);
     }
+
+    #[test]
+    fn queries() {
+        let g = graph(data_p2());
+        assert_eq!(g.depth("SAN"), Some(5));
+        assert_eq!(g.parent("SAN"), Some("I"));
+        assert_eq!(g.parent("COM"), None);
+        assert_eq!(g.distance("B", "L"), Ok(6));
+        assert_eq!(g.distance("L", "L"), Ok(0));
+        assert_eq!(g.distance("L", "X"), Err(Error::Unknown("X".to_string())));
+        assert_eq!(
+            g.path_between("YOU", "SAN"),
+            Ok(vec!["YOU", "K", "J", "E", "D", "I", "SAN"])
+        );
+        assert_eq!(g.path_between("C", "E"), Ok(vec!["C", "D", "E"]));
+        assert_eq!(g.path_between("H", "H"), Ok(vec!["H"]));
+        assert_eq!(g.transfers("YOU", "SAN"), Ok(4));
+        assert_eq!(
+            g.transfers("COM", "SAN"),
+            Err(Error::OrbitsNothing("COM".to_string()))
+        );
+
+        let pairs: Vec<_> = ["COM", "B", "G", "H", "D", "I", "L", "YOU", "SAN", "X"]
+            .iter()
+            .flat_map(|&a| ["K", "H", "YOU", "COM", "X"].iter().map(move |&b| (a, b)))
+            .collect();
+        let expected: Vec<_> = pairs.iter().map(|(a, b)| g.distance(a, b)).collect();
+        assert_eq!(g.distances(&pairs), expected);
+        let index = g.ancestry();
+        for (a, b) in pairs {
+            assert_eq!(index.common_ancestor(a, b), g.common_ancestor(a, b));
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        let parse = |data: &[&str]| Parser::parse(&lines(data)).map(|_| ());
+        assert!(matches!(
+            parse(&["COM)B", "B C"]),
+            Err(Error::MissingParenthesis(2))
+        ));
+        assert!(matches!(parse(&["COM)"]), Err(Error::EmptyName(1))));
+        assert!(matches!(
+            parse(&["COM)B", "COM)C", "C)B"]),
+            Err(Error::DuplicateParent(3))
+        ));
+        assert!(parse(&["COM)B", "COM)B"]).is_ok());
+
+        let build = |data: &[&str]| Graph::new(Parser::parse(&lines(data)).unwrap()).map(|_| ());
+        assert!(matches!(
+            build(&["COM)B", "C)D", "D)C"]),
+            Err(Error::Cycle(_))
+        ));
+        assert!(matches!(build(&["COM)B", "C)C"]), Err(Error::Cycle(_))));
+        // Either object of a two-object cycle may be the one reported.
+        match build(&["COM)B", "A)B2", "B2)A"]) {
+            Err(e @ Error::Cycle(_)) => assert!(
+                e.to_string() == "Orbit cycle through A"
+                    || e.to_string() == "Orbit cycle through B2"
+            ),
+            _ => panic!("expected a cycle"),
+        }
+        match build(&["COM)B", "X)Y", "Y)Z"]) {
+            Err(Error::Unrooted(obj)) => assert_eq!(obj, "X"),
+            _ => panic!("expected an unrooted object"),
+        }
+        assert_eq!(
+            Error::MissingParenthesis(2).to_string(),
+            "Missing parenthesis on line 2"
+        );
+    }
+
+    #[test]
+    fn deep_chain() {
+        let n = 200_000;
+        let v: Vec<_> = (0..n)
+            .map(|i| match i {
+                0 => "COM)0".to_string(),
+                _ => format!("{}){}", i - 1, i),
+            })
+            .collect();
+        let g = Graph::new(Parser::parse(&v).unwrap()).unwrap();
+        let depths = g.traverse();
+        assert_eq!(depths[&(n - 1).to_string()], n);
+        assert_eq!(depths.values().sum::<usize>(), n * (n + 1) / 2);
+        assert_eq!(
+            g.distances(&[("COM", "199999"), ("1", "0")]),
+            [Ok(n), Ok(1)]
+        );
+    }
+
+    #[test]
+    fn forest() {
+        let mut v = lines(&["P)Q", "Q)R", "P)S", "X)Y", "SUN)EARTH", "EARTH)MOON"]);
+        assert!(matches!(
+            Graph::new(Parser::parse(&v).unwrap()),
+            Err(Error::Unrooted(_))
+        ));
+        v.extend(lines(data_p2().lines().collect::<Vec<_>>().as_slice()));
+        let g = Graph::forest(Parser::parse(&v).unwrap()).unwrap();
+        assert_eq!(g.roots(), ["COM", "P", "SUN", "X"]);
+        let system = |root: &str, objects, orbits, depth| System {
+            root: root.to_string(),
+            objects,
+            orbits,
+            depth,
+        };
+        assert_eq!(
+            g.systems(),
+            [
+                system("COM", 14, 54, 7),
+                system("P", 4, 4, 2),
+                system("SUN", 3, 3, 2),
+                system("X", 2, 1, 1),
+            ]
+        );
+        assert_eq!(g.depth("MOON"), Some(2));
+        assert_eq!(g.distance("R", "S"), Ok(3));
+        assert_eq!(g.transfers("YOU", "SAN"), Ok(4));
+        let unreachable = Err(Error::Unreachable("MOON".to_string(), "SAN".to_string()));
+        assert_eq!(g.distance("MOON", "SAN"), unreachable);
+        assert_eq!(g.distances(&[("MOON", "SAN")]), [unreachable]);
+        assert_eq!(
+            g.ancestry().common_ancestor("R", "Y"),
+            Err(Error::Unreachable("R".to_string(), "Y".to_string()))
+        );
+        assert_eq!(g.ancestry().common_ancestor("R", "S"), Ok("P"));
+        assert!(Graph::forest(Parser::parse(&lines(&["P)Q", "Q)P"])).unwrap()).is_err());
+    }
 }
@@ -0,0 +1,188 @@
+use super::{Error, Graph};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+/// The objects to pick out in a drawing: those on the path between two objects, and the common
+/// ancestor where the path turns.
+struct Highlight<'a> {
+    path: HashSet<&'a str>,
+    edges: HashSet<(&'a str, &'a str)>,
+    ancestor: Option<&'a str>,
+}
+
+impl<'a> Highlight<'a> {
+    fn new(g: &'a Graph, ends: Option<(&str, &str)>) -> Result<Self, Error> {
+        let (path, ancestor) = match ends {
+            Some((a, b)) => (g.path_between(a, b)?, Some(g.common_ancestor(a, b)?)),
+            None => (Vec::new(), None),
+        };
+        let edges = path
+            .windows(2)
+            .map(|w| match g.parent(w[0]) {
+                Some(p) if p == w[1] => (w[1], w[0]),
+                _ => (w[0], w[1]),
+            })
+            .collect();
+        Ok(Highlight {
+            path: path.into_iter().collect(),
+            edges,
+            ancestor,
+        })
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Graph {
+    // The objects orbiting each object, in order of name.
+    fn children(&self) -> HashMap<&str, Vec<&str>> {
+        let mut map: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (i, obj) in self.names.iter().enumerate() {
+            if let Some(p) = self.parent_of(i) {
+                map.entry(&*self.names[p]).or_default().push(&**obj);
+            }
+        }
+        for v in map.values_mut() {
+            v.sort_unstable();
+        }
+        map
+    }
+
+    /// Writes the graph in Graphviz DOT format, with an edge from each object to those orbiting it.
+    ///
+    /// If `highlight` names two objects, the path between them is drawn in red and their common
+    /// ancestor is drawn with a double outline.
+    pub fn write_dot<W: Write>(
+        &self,
+        w: &mut W,
+        highlight: Option<(&str, &str)>,
+    ) -> io::Result<()> {
+        let h = Highlight::new(self, highlight)?;
+        let children = self.children();
+        writeln!(w, "digraph orbits {{")?;
+        let mut objects: Vec<_> = self.names.iter().map(|s| &**s).collect();
+        objects.sort_unstable();
+        for obj in objects {
+            if h.ancestor == Some(obj) {
+                writeln!(w, "  {} [color=red, peripheries=2];", quote(obj))?;
+            } else if h.path.contains(obj) {
+                writeln!(w, "  {} [color=red];", quote(obj))?;
+            } else {
+                writeln!(w, "  {};", quote(obj))?;
+            }
+            for &child in children.get(obj).into_iter().flatten() {
+                if h.edges.contains(&(obj, child)) {
+                    writeln!(
+                        w,
+                        "  {} -> {} [color=red, penwidth=2];",
+                        quote(obj),
+                        quote(child)
+                    )?;
+                } else {
+                    writeln!(w, "  {} -> {};", quote(obj), quote(child))?;
+                }
+            }
+        }
+        writeln!(w, "}}")
+    }
+
+    /// Writes the graph as an indented tree, one object to a line, with each root at the left.
+    ///
+    /// If `highlight` names two objects, those on the path between them are marked with `*` and
+    /// their common ancestor is labelled.
+    pub fn write_tree<W: Write>(
+        &self,
+        w: &mut W,
+        highlight: Option<(&str, &str)>,
+    ) -> io::Result<()> {
+        let h = Highlight::new(self, highlight)?;
+        let children = self.children();
+        // Each entry holds an object and the prefix for its line; an explicit stack keeps deep
+        // chains from overflowing the call stack.
+        let mut stack: Vec<(&str, String, &str)> = self
+            .roots()
+            .into_iter()
+            .rev()
+            .map(|r| (r, String::new(), ""))
+            .collect();
+        while let Some((obj, indent, branch)) = stack.pop() {
+            write!(w, "{}{}{}", indent, branch, obj)?;
+            if h.ancestor == Some(obj) {
+                write!(w, " * (common ancestor)")?;
+            } else if h.path.contains(obj) {
+                write!(w, " *")?;
+            }
+            writeln!(w)?;
+            let below = match branch {
+                "" => indent,
+                "+-- " => indent + "|   ",
+                _ => indent + "    ",
+            };
+            let kids = children.get(obj).map(|v| v.as_slice()).unwrap_or(&[]);
+            for (i, &child) in kids.iter().enumerate().rev() {
+                let branch = if i + 1 == kids.len() { "`-- " } else { "+-- " };
+                stack.push((child, below.clone(), branch));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Graph, Parser};
+
+    fn graph() -> Graph {
+        let v: Vec<_> = ["COM)B", "B)C", "C)D", "B)G", "D)YOU", "G)SAN", "X)Y"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        Graph::forest(Parser::parse(&v).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn dot() {
+        let mut v = Vec::new();
+        graph().write_dot(&mut v, Some(("YOU", "SAN"))).unwrap();
+        let s = String::from_utf8(v).unwrap();
+        assert!(s.starts_with("digraph orbits {\n"));
+        assert!(s.ends_with("}\n"));
+        assert!(s.contains("  \"B\" [color=red, peripheries=2];\n"));
+        assert!(s.contains("  \"YOU\" [color=red];\n"));
+        assert!(s.contains("  \"COM\";\n"));
+        assert!(s.contains("  \"COM\" -> \"B\";\n"));
+        assert!(s.contains("  \"B\" -> \"G\" [color=red, penwidth=2];\n"));
+        assert!(s.contains("  \"X\" -> \"Y\";\n"));
+        assert_eq!(s.matches(" -> ").count(), 7);
+        assert!(graph()
+            .write_dot(&mut Vec::new(), Some(("YOU", "Y")))
+            .is_err());
+    }
+
+    #[test]
+    fn tree() {
+        let mut v = Vec::new();
+        graph().write_tree(&mut v, None).unwrap();
+        assert_eq!(
+            String::from_utf8(v).unwrap(),
+            "COM\n\
+             `-- B\n    \
+                 +-- C\n    \
+                 |   `-- D\n    \
+                 |       `-- YOU\n    \
+                 `-- G\n        \
+                     `-- SAN\n\
+             X\n\
+             `-- Y\n"
+        );
+        let mut v = Vec::new();
+        graph().write_tree(&mut v, Some(("YOU", "SAN"))).unwrap();
+        let s = String::from_utf8(v).unwrap();
+        assert!(s.contains("`-- B * (common ancestor)\n"));
+        assert!(s.contains("+-- C *\n"));
+        assert!(s.contains("`-- SAN *\n"));
+        assert!(s.contains("`-- Y\n"));
+    }
+}
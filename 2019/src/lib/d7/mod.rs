@@ -1,97 +1,237 @@
 use crate::d2;
-use permutator::Permutation;
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::cmp;
+use std::error;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::thread;
 
-pub struct Chain<'a> {
-    prog: &'a str,
+mod network;
+mod search;
+
+pub use self::network::Network;
+pub use self::search::Space;
+
+#[derive(Debug)]
+pub enum Error {
+    DuplicateInput(usize),
+    ExpectedArrow(usize),
+    InvalidName(usize),
+    InvalidProgram(usize),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::DuplicateInput(line) => write!(f, "Repeated input link on line {}", line),
+            Error::ExpectedArrow(line) => write!(f, "Expected an arrow on line {}", line),
+            Error::InvalidName(line) => write!(f, "Invalid amplifier name on line {}", line),
+            Error::InvalidProgram(line) => write!(f, "Invalid program number on line {}", line),
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(val: Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, val)
+    }
+}
+
+pub struct Chain {
+    code: Arc<Vec<i64>>,
     initial: i64,
 }
 
-impl<'a> Chain<'a> {
-    pub fn new(prog: &'a str, initial: i64) -> Self {
-        Chain { prog, initial }
+impl Chain {
+    /// Parses `prog` once for every run of the amplifiers, which share its memory until they
+    /// write to it.
+    pub fn new(prog: &str, initial: i64) -> Self {
+        Chain {
+            code: Arc::new(d2::Parser::parse(prog)),
+            initial,
+        }
     }
 
+    /// Runs the amplifiers in a line, returning what the last one outputs.
     pub fn run(&self, phases: Vec<i64>) -> Result<Vec<i64>, d2::Error> {
-        let code = d2::Parser::parse(self.prog);
-        let mut interps = vec![d2::Program::new(code); phases.len()];
-        let mut v = Box::new(vec![self.initial]);
-        for (interp, &phase) in interps.iter_mut().zip(phases.iter()) {
-            v.insert(0, phase);
-            let r = interp
-                .run(Rc::new(RefCell::new(v.into_iter())))
-                .collect::<Result<Vec<_>, d2::Error>>()?;
-            v = Box::new(r);
-        }
-        Ok(*v)
+        self.run_network(&Network::chain(phases.len()), phases)
     }
 
+    /// Runs the amplifiers in a loop, returning everything the last one outputs.
     pub fn run_with_feedback(&self, phases: Vec<i64>) -> Result<Vec<i64>, d2::Error> {
-        let mut inp = vec![self.initial];
-        loop {
-            let code = d2::Parser::parse(self.prog);
-            let mut interps = vec![d2::Program::new(code); phases.len()];
-            let mut v = Box::new(inp.clone());
-            for (interp, &phase) in interps.iter_mut().zip(phases.iter()) {
-                v.insert(0, phase);
-                let r = interp
-                    .run(Rc::new(RefCell::new(v.into_iter())))
-                    .filter(|r| r.is_ok())
-                    .collect::<Result<Vec<_>, d2::Error>>()?;
-                v = Box::new(r);
-            }
-            if v.len() + 1 == inp.len() {
-                return Ok(*v);
-            }
-            inp.push(v[inp.len() - 1]);
+        self.run_network(&Network::feedback_loop(phases.len()), phases)
+    }
+
+    // Runs a single amplifier, returning the first signal it outputs.
+    fn step(&self, phase: i64, signal: i64) -> Result<i64, d2::Error> {
+        let v = Network::chain(1).run(std::slice::from_ref(&self.code), &[phase], signal)?;
+        v.first().copied().ok_or(d2::Error::OutOfData)
+    }
+
+    /// Runs every amplifier of `network` on this chain's program.
+    pub fn run_network(&self, network: &Network, phases: Vec<i64>) -> Result<Vec<i64>, d2::Error> {
+        network.run(std::slice::from_ref(&self.code), &phases, self.initial)
+    }
+}
+
+/// What makes one phase setting better than another.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Objective {
+    /// The largest signal is best.
+    Max,
+    /// The smallest signal is best.
+    Min,
+    /// The signal closest to this value is best.
+    Target(i64),
+}
+
+impl Objective {
+    // A key for sorting signals from best to worst.
+    fn key(self, signal: i64) -> i128 {
+        match self {
+            Objective::Max => -(signal as i128),
+            Objective::Min => signal as i128,
+            Objective::Target(t) => (signal as i128 - t as i128).abs(),
         }
     }
 }
 
-pub struct Searcher<'a> {
-    chain: Chain<'a>,
-    inputs: Vec<i64>,
+/// A setting of the phases of the amplifiers, in order, and the signal it produces.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Outcome {
+    pub phases: Vec<i64>,
+    pub signal: i64,
 }
 
-impl<'a> Searcher<'a> {
-    pub fn new(chain: Chain<'a>, inputs: Vec<i64>) -> Self {
-        Searcher { chain, inputs }
+pub struct Searcher {
+    chain: Chain,
+    space: Space,
+    feedback: bool,
+    prune: bool,
+    threads: usize,
+}
+
+impl Searcher {
+    /// Searches the orders of `inputs`, using as many threads as there are processors.
+    pub fn new(chain: Chain, inputs: Vec<i64>) -> Self {
+        Self::with_space(chain, Space::Permutations(inputs))
     }
 
-    pub fn outputs(&self) -> Vec<i64> {
-        let mut v = self.inputs.clone();
-        // The permutation method doesn't include the identity permutation, so chain the iterators
-        // together.
-        vec![self.inputs.clone()]
+    /// Searches the settings in `space`, using as many threads as there are processors.
+    pub fn with_space(chain: Chain, space: Space) -> Self {
+        Searcher {
+            chain,
+            space,
+            feedback: false,
+            prune: false,
+            threads: thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        }
+    }
+
+    /// Makes `ranked` and `best` run the amplifiers in a feedback loop, taking the last signal
+    /// the final amplifier produces.
+    pub fn with_feedback(mut self) -> Self {
+        self.feedback = true;
+        self
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = cmp::max(threads, 1);
+        self
+    }
+
+    /// Makes `best` build settings a phase at a time, abandoning a partial setting as soon as
+    /// another which leaves the same phases to choose gives a better signal so far.
+    ///
+    /// This only applies to a chain without feedback and to the `Max` and `Min` objectives, and
+    /// is only right if each amplifier's output rises as its input signal rises, as it does for
+    /// the puzzle's amplifiers.
+    pub fn with_pruning(mut self) -> Self {
+        self.prune = true;
+        self
+    }
+
+    // Applies `f` to every phase setting, split between the threads, keeping the results in the
+    // order of the settings.
+    fn evaluate<T: Send, F: Fn(Vec<i64>) -> T + Sync>(&self, f: F) -> Vec<T> {
+        let phases = self.space.settings();
+        if self.threads == 1 {
+            return phases.into_iter().map(f).collect();
+        }
+        let size = cmp::max(phases.len().div_ceil(self.threads), 1);
+        let f = &f;
+        thread::scope(|s| {
+            let handles: Vec<_> = phases
+                .chunks(size)
+                .map(|c| s.spawn(move || c.iter().cloned().map(f).collect::<Vec<_>>()))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        })
+    }
+
+    fn signal(&self, phases: Vec<i64>) -> Result<i64, d2::Error> {
+        if self.feedback {
+            let v = self.chain.run_with_feedback(phases)?;
+            v.last().copied().ok_or(d2::Error::OutOfData)
+        } else {
+            let v = self.chain.run(phases)?;
+            v.first().copied().ok_or(d2::Error::OutOfData)
+        }
+    }
+
+    /// Every phase setting with its signal, from best to worst according to `objective`.
+    ///
+    /// Settings with equally good signals are in order of their phases.
+    pub fn ranked(&self, objective: Objective) -> Result<Vec<Outcome>, d2::Error> {
+        let mut v = self
+            .evaluate(|phases| {
+                let signal = self.signal(phases.clone())?;
+                Ok(Outcome { phases, signal })
+            })
             .into_iter()
-            .chain(v.permutation())
-            .map(|phases| self.chain.run(phases).unwrap()[0])
-            .collect()
+            .collect::<Result<Vec<_>, d2::Error>>()?;
+        v.sort_by(|a, b| {
+            let key = |o: &Outcome| (objective.key(o.signal), o.phases.clone());
+            key(a).cmp(&key(b))
+        });
+        Ok(v)
+    }
+
+    /// The best phase setting according to `objective`, or `None` if there are no phases.
+    pub fn best(&self, objective: Objective) -> Result<Option<Outcome>, d2::Error> {
+        let target = matches!(objective, Objective::Target(_));
+        if self.prune && !self.feedback && !target {
+            return self.best_pruned(objective);
+        }
+        Ok(self.ranked(objective)?.into_iter().next())
+    }
+
+    pub fn outputs(&self) -> Vec<i64> {
+        self.evaluate(|phases| self.chain.run(phases).unwrap()[0])
     }
 
     pub fn outputs_with_feedback(&self) -> Vec<i64> {
-        let mut v = self.inputs.clone();
-        // The permutation method doesn't include the identity permutation, so chain the iterators
-        // together.
-        vec![self.inputs.clone()]
-            .into_iter()
-            .chain(v.permutation())
-            .map(|phases| {
-                *self
-                    .chain
-                    .run_with_feedback(phases)
-                    .unwrap()
-                    .last()
-                    .unwrap()
-            })
-            .collect()
+        self.evaluate(|phases| {
+            *self
+                .chain
+                .run_with_feedback(phases)
+                .unwrap()
+                .last()
+                .unwrap()
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Chain, Searcher};
+    use super::{Chain, Objective, Outcome, Searcher};
 
     fn find_max(prog: &str) -> i64 {
         let inputs = vec![0, 1, 2, 3, 4];
@@ -130,4 +270,63 @@ mod tests {
             18216
         );
     }
+
+    #[test]
+    fn best() {
+        let prog = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+        let s = Searcher::new(Chain::new(prog, 0), vec![0, 1, 2, 3, 4]);
+        assert_eq!(
+            s.best(Objective::Max).unwrap(),
+            Some(Outcome {
+                phases: vec![4, 3, 2, 1, 0],
+                signal: 43210
+            })
+        );
+        assert_eq!(
+            s.best(Objective::Min).unwrap(),
+            Some(Outcome {
+                phases: vec![0, 1, 2, 3, 4],
+                signal: 1234
+            })
+        );
+        assert_eq!(
+            s.best(Objective::Target(20000)).unwrap().unwrap().signal,
+            20134
+        );
+
+        let ranked = s.ranked(Objective::Max).unwrap();
+        assert_eq!(ranked.len(), 120);
+        assert!(ranked.windows(2).all(|w| w[0].signal >= w[1].signal));
+        let mut phases: Vec<_> = ranked.into_iter().map(|o| o.phases).collect();
+        phases.sort();
+        phases.dedup();
+        assert_eq!(phases.len(), 120);
+
+        let prog =
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5";
+        let s = Searcher::new(Chain::new(prog, 0), vec![5, 6, 7, 8, 9]).with_feedback();
+        assert_eq!(
+            s.best(Objective::Max).unwrap(),
+            Some(Outcome {
+                phases: vec![9, 8, 7, 6, 5],
+                signal: 139629729
+            })
+        );
+    }
+
+    #[test]
+    fn threads() {
+        let prog = "3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0";
+        let ranked = |threads| {
+            Searcher::new(Chain::new(prog, 0), vec![0, 1, 2, 3, 4])
+                .with_threads(threads)
+                .ranked(Objective::Max)
+                .unwrap()
+        };
+        let serial = ranked(1);
+        assert_eq!(serial[0].signal, 65210);
+        for threads in &[2, 7, 200] {
+            assert_eq!(ranked(*threads), serial);
+        }
+    }
 }
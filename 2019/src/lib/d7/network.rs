@@ -0,0 +1,302 @@
+use super::Error;
+use crate::d2;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Where an amplifier sends its output.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Target {
+    Amplifier(usize),
+    Output,
+}
+
+// The input waiting for an amplifier, which others can add to between its runs.
+struct Inbox(VecDeque<i64>);
+
+impl Iterator for Inbox {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        self.0.pop_front()
+    }
+}
+
+/// A network of amplifiers, each running an Intcode program and sending every value it outputs
+/// on to other amplifiers or to the output of the network.
+///
+/// Each amplifier receives its phase first, then the initial signal if it's fed from the input,
+/// and then whatever the other amplifiers send it, in the order they send it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Network {
+    names: Vec<String>,
+    // The index of the program each amplifier runs.
+    programs: Vec<usize>,
+    links: Vec<Vec<Target>>,
+    inputs: Vec<usize>,
+}
+
+impl Network {
+    fn empty() -> Self {
+        Network {
+            names: Vec::new(),
+            programs: Vec::new(),
+            links: Vec::new(),
+            inputs: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, name: &str) -> usize {
+        self.names.push(name.to_string());
+        self.programs.push(0);
+        self.links.push(Vec::new());
+        self.names.len() - 1
+    }
+
+    /// `n` amplifiers in a line, named from `A`, with the input feeding the first and the last
+    /// feeding the output.
+    pub fn chain(n: usize) -> Self {
+        let mut net = Self::empty();
+        for i in 0..n {
+            let name = match i {
+                0..=25 => ((b'A' + i as u8) as char).to_string(),
+                _ => format!("A{}", i),
+            };
+            net.add(&name);
+            if i > 0 {
+                net.links[i - 1].push(Target::Amplifier(i));
+            }
+        }
+        if n > 0 {
+            net.inputs.push(0);
+            net.links[n - 1].push(Target::Output);
+        }
+        net
+    }
+
+    /// As for `chain`, but with the last amplifier feeding the first as well as the output.
+    pub fn feedback_loop(n: usize) -> Self {
+        let mut net = Self::chain(n);
+        if n > 0 {
+            net.links[n - 1].push(Target::Amplifier(0));
+        }
+        net
+    }
+
+    /// Parses a description of a network, one link to a line.
+    ///
+    /// A line such as `A -> B, C` sends everything amplifier A outputs to both B and C.  The names
+    /// `input` and `output` stand for the input and the output of the network.  A line such as
+    /// `B uses 1` makes B run the program with that index instead of the first.  Amplifiers are
+    /// given their phases in the order their names first appear.  The input can feed each
+    /// amplifier only once.  Blank lines and anything after a `#` are ignored.  Errors carry the
+    /// line number, starting from 1.
+    pub fn parse(data: &str) -> Result<Self, Error> {
+        let mut net = Self::empty();
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        for (i, line) in data.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut id = |name: &str| -> Result<usize, Error> {
+                let valid = !name.is_empty()
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    && name != "input"
+                    && name != "output";
+                if !valid {
+                    return Err(Error::InvalidName(line_no));
+                }
+                Ok(*ids.entry(name.to_string()).or_insert_with(|| net.add(name)))
+            };
+
+            let words: Vec<_> = line.split_whitespace().collect();
+            if let [name, "uses", program] = words[..] {
+                let amp = id(name)?;
+                let program = program
+                    .parse()
+                    .map_err(|_| Error::InvalidProgram(line_no))?;
+                net.programs[amp] = program;
+                continue;
+            }
+
+            let mut parts = line.splitn(2, "->");
+            let (from, to) = match (parts.next(), parts.next()) {
+                (Some(from), Some(to)) => (from.trim(), to),
+                _ => return Err(Error::ExpectedArrow(line_no)),
+            };
+            let from = match from {
+                "input" => None,
+                _ => Some(id(from)?),
+            };
+            let mut targets = Vec::new();
+            for name in to.split(',').map(|s| s.trim()) {
+                targets.push(match (from, name) {
+                    (None, "output") => return Err(Error::InvalidName(line_no)),
+                    (_, "output") => Target::Output,
+                    _ => Target::Amplifier(id(name)?),
+                });
+            }
+            for t in targets {
+                match (from, t) {
+                    (None, Target::Amplifier(j)) if net.inputs.contains(&j) => {
+                        return Err(Error::DuplicateInput(line_no))
+                    }
+                    (None, Target::Amplifier(j)) => net.inputs.push(j),
+                    (Some(i), t) => net.links[i].push(t),
+                    (None, Target::Output) => unreachable!(),
+                }
+            }
+        }
+        Ok(net)
+    }
+
+    /// The number of amplifiers, and so of phases a run needs.
+    pub fn amplifiers(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Runs the network until every amplifier halts, returning the values sent to the output.
+    ///
+    /// Amplifiers take turns, each running until it halts or needs input which hasn't arrived yet.
+    /// If they all end up waiting for input, the network can never finish, which is an error.
+    ///
+    /// Panics if there isn't one phase for each amplifier or an amplifier uses a program which
+    /// isn't in `programs`.
+    pub fn run(
+        &self,
+        programs: &[Arc<Vec<i64>>],
+        phases: &[i64],
+        initial: i64,
+    ) -> Result<Vec<i64>, d2::Error> {
+        let n = self.amplifiers();
+        assert_eq!(phases.len(), n, "need one phase for each amplifier");
+        let mut amps: Vec<_> = self
+            .programs
+            .iter()
+            .map(|&p| d2::Program::new(programs[p].clone()))
+            .collect();
+        let inboxes: Vec<_> = phases
+            .iter()
+            .map(|&p| Rc::new(RefCell::new(Inbox(vec![p].into()))))
+            .collect();
+        for &i in &self.inputs {
+            inboxes[i].borrow_mut().0.push_back(initial);
+        }
+
+        let mut halted = vec![false; n];
+        let mut out = Vec::new();
+        while halted.iter().any(|h| !h) {
+            let mut progress = false;
+            for i in 0..n {
+                if halted[i] {
+                    continue;
+                }
+                let waiting = inboxes[i].borrow().0.len();
+                let mut values = Vec::new();
+                halted[i] = true;
+                for r in amps[i].resume(inboxes[i].clone()) {
+                    match r {
+                        Ok(v) => values.push(v),
+                        Err(d2::Error::OutOfData) => halted[i] = false,
+                    }
+                }
+                progress |= halted[i] || !values.is_empty();
+                progress |= inboxes[i].borrow().0.len() != waiting;
+                for v in values {
+                    for t in &self.links[i] {
+                        match *t {
+                            Target::Amplifier(j) => inboxes[j].borrow_mut().0.push_back(v),
+                            Target::Output => out.push(v),
+                        }
+                    }
+                }
+            }
+            if !progress {
+                return Err(d2::Error::OutOfData);
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Error;
+    use super::Network;
+    use crate::d2;
+    use std::sync::Arc;
+
+    fn program(s: &str) -> Arc<Vec<i64>> {
+        Arc::new(d2::Parser::parse(s))
+    }
+
+    // Outputs ten times its second input plus its first.
+    const SHIFT: &str = "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0";
+    // Outputs the sum of its second and third inputs.
+    const ADD: &str = "3,20,3,21,3,22,1,21,22,23,4,23,99,0,0,0,0,0,0,0,0,0,0,0";
+
+    #[test]
+    fn presets() {
+        let prog = vec![program(SHIFT)];
+        let chain = Network::chain(5);
+        assert_eq!(chain.names(), ["A", "B", "C", "D", "E"]);
+        assert_eq!(chain.run(&prog, &[4, 3, 2, 1, 0], 0).unwrap(), [43210]);
+        let parsed = Network::parse("input -> A\nA -> B\nB -> C\nC -> D\nD -> E\nE -> output\n");
+        assert_eq!(parsed.unwrap(), chain);
+
+        let prog = vec![program(
+            "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,1005,28,6,99,0,0,5",
+        )];
+        let looped = Network::feedback_loop(5);
+        let out = looped.run(&prog, &[9, 8, 7, 6, 5], 0).unwrap();
+        assert_eq!(out.last(), Some(&139629729));
+        let parsed = Network::parse("input -> A\nA -> B\nB -> C\nC -> D\nD -> E\nE -> output, A");
+        assert_eq!(parsed.unwrap(), looped);
+    }
+
+    #[test]
+    fn fan_out() {
+        // The input goes to A, which feeds both B and C; D adds what they send it.
+        let net = Network::parse(
+            "# A diamond\n\
+             input -> A\n\
+             A -> B, C\n\
+             B -> D\n\
+             C -> D\n\
+             D -> output\n\
+             D uses 1\n",
+        )
+        .unwrap();
+        assert_eq!(net.names(), ["A", "B", "C", "D"]);
+        let progs = vec![program(SHIFT), program(ADD)];
+        // A makes 10 + 1, B makes 110 + 2 and C 110 + 3, and D adds those.
+        assert_eq!(net.run(&progs, &[1, 2, 3, 0], 1).unwrap(), [225]);
+    }
+
+    #[test]
+    fn errors() {
+        let parse = |s| Network::parse(s).unwrap_err();
+        assert!(matches!(parse("A -> B\nA B"), Error::ExpectedArrow(2)));
+        assert!(matches!(parse("input -> output"), Error::InvalidName(1)));
+        assert!(matches!(parse("output -> A"), Error::InvalidName(1)));
+        assert!(matches!(parse("A -> B C"), Error::InvalidName(1)));
+        assert!(matches!(parse("A uses x"), Error::InvalidProgram(1)));
+        assert!(matches!(
+            parse("input -> A\nA -> B\ninput -> B, A"),
+            Error::DuplicateInput(3)
+        ));
+        assert!(matches!(parse("input -> A, A"), Error::DuplicateInput(1)));
+
+        // Each waits for the other, since neither is fed from the input.
+        let net = Network::parse("A -> B\nB -> A, output\n").unwrap();
+        let prog = vec![program(SHIFT)];
+        assert!(net.run(&prog, &[1, 2], 0).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use super::{d2, Objective, Outcome, Searcher};
+use std::collections::BTreeMap;
+
+/// The phase settings a search considers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Space {
+    /// Every order of these phases, each used once.
+    Permutations(Vec<i64>),
+    /// Every way of choosing `len` of these phases, each any number of times, keeping them in the
+    /// order they're listed.
+    Combinations(Vec<i64>, usize),
+    /// Every sequence of `len` phases from `lo` to `hi` inclusive.
+    Range(i64, i64, usize),
+}
+
+impl Space {
+    /// The number of phases in each setting, and so of amplifiers.
+    pub fn len(&self) -> usize {
+        match self {
+            Space::Permutations(v) => v.len(),
+            Space::Combinations(_, len) | Space::Range(_, _, len) => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The phases which can come next after `prefix`, in order.
+    fn choices(&self, prefix: &[i64]) -> Vec<i64> {
+        let mut v = match self {
+            Space::Permutations(phases) => phases
+                .iter()
+                .filter(|&&p| {
+                    let used = prefix.iter().filter(|&&q| q == p).count();
+                    used < phases.iter().filter(|&&q| q == p).count()
+                })
+                .copied()
+                .collect(),
+            Space::Combinations(phases, _) => {
+                let start = prefix
+                    .last()
+                    .and_then(|last| phases.iter().position(|p| p == last))
+                    .unwrap_or(0);
+                phases[start..].to_vec()
+            }
+            Space::Range(lo, hi, _) => (*lo..=*hi).collect(),
+        };
+        // Repeated phases would only give the same settings again.
+        let mut seen = Vec::new();
+        v.retain(|p| {
+            let new = !seen.contains(p);
+            seen.push(*p);
+            new
+        });
+        v
+    }
+
+    // What the choices after `prefix` depend on: two prefixes with the same state can be followed
+    // by the same phases.
+    fn state(&self, prefix: &[i64]) -> Vec<i64> {
+        match self {
+            Space::Permutations(_) => {
+                let mut v = prefix.to_vec();
+                v.sort_unstable();
+                v
+            }
+            Space::Combinations(..) => prefix.last().copied().into_iter().collect(),
+            Space::Range(..) => Vec::new(),
+        }
+    }
+
+    /// Every setting, in order of the choices at each position.
+    pub fn settings(&self) -> Vec<Vec<i64>> {
+        let mut out = Vec::new();
+        let mut stack = vec![Vec::new()];
+        while let Some(prefix) = stack.pop() {
+            if prefix.len() == self.len() {
+                out.push(prefix);
+                continue;
+            }
+            for p in self.choices(&prefix).into_iter().rev() {
+                let mut next = prefix.clone();
+                next.push(p);
+                stack.push(next);
+            }
+        }
+        out
+    }
+}
+
+impl Searcher {
+    /// Finds the best setting for a chain of amplifiers a position at a time, dropping any partial
+    /// setting which another with the same choices left beats at that point.
+    ///
+    /// This is only right if every amplifier's output moves in the same direction as its input
+    /// signal, which is how the puzzle's amplifiers behave.
+    pub(super) fn best_pruned(&self, objective: Objective) -> Result<Option<Outcome>, d2::Error> {
+        let key = |o: &Outcome| (objective.key(o.signal), o.phases.clone());
+        let mut frontier = vec![Outcome {
+            phases: Vec::new(),
+            signal: self.chain.initial,
+        }];
+        for _ in 0..self.space.len() {
+            let mut next: BTreeMap<Vec<i64>, Outcome> = BTreeMap::new();
+            for o in frontier {
+                for p in self.space.choices(&o.phases) {
+                    let mut phases = o.phases.clone();
+                    phases.push(p);
+                    let signal = self.chain.step(p, o.signal)?;
+                    let candidate = Outcome { phases, signal };
+                    let state = self.space.state(&candidate.phases);
+                    match next.get(&state) {
+                        Some(best) if key(best) <= key(&candidate) => (),
+                        _ => {
+                            next.insert(state, candidate);
+                        }
+                    }
+                }
+            }
+            frontier = next.into_values().collect();
+        }
+        Ok(frontier.into_iter().min_by_key(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Chain, Objective, Searcher};
+    use super::Space;
+
+    #[test]
+    fn settings() {
+        let perms = Space::Permutations(vec![0, 1, 2]).settings();
+        assert_eq!(
+            perms,
+            [
+                [0, 1, 2],
+                [0, 2, 1],
+                [1, 0, 2],
+                [1, 2, 0],
+                [2, 0, 1],
+                [2, 1, 0]
+            ]
+        );
+        assert_eq!(
+            Space::Permutations(vec![5, 6, 7, 8, 9]).settings().len(),
+            120
+        );
+        assert_eq!(Space::Permutations(vec![1, 1, 2]).settings().len(), 3);
+        assert_eq!(
+            Space::Combinations(vec![3, 1, 2], 2).settings(),
+            [[3, 3], [3, 1], [3, 2], [1, 1], [1, 2], [2, 2]]
+        );
+        // C(5 + 3 - 1, 3) multisets.
+        assert_eq!(
+            Space::Combinations(vec![0, 1, 2, 3, 4], 3).settings().len(),
+            35
+        );
+        assert_eq!(Space::Range(-1, 1, 2).settings().len(), 9);
+        assert_eq!(Space::Range(-1, 1, 2).settings()[1], [-1, 0]);
+        assert_eq!(Space::Range(2, 1, 2).settings().len(), 0);
+        assert_eq!(Space::Range(0, 9, 0).settings(), [Vec::<i64>::new()]);
+    }
+
+    #[test]
+    fn pruning() {
+        let progs = [
+            "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0",
+            "3,23,3,24,1002,24,10,24,1002,23,-1,23,101,5,23,23,1,24,23,23,4,23,99,0,0",
+            "3,31,3,32,1002,32,10,32,1001,31,-2,31,1007,31,0,33,1002,33,7,33,1,33,31,31,1,32,31,31,4,31,99,0,0,0",
+        ];
+        let spaces = [
+            Space::Permutations(vec![0, 1, 2, 3, 4]),
+            Space::Combinations(vec![0, 1, 2, 3, 4], 5),
+            Space::Range(0, 4, 4),
+        ];
+        for prog in &progs {
+            for space in &spaces {
+                for &objective in &[Objective::Max, Objective::Min] {
+                    let s = |prune| {
+                        let s = Searcher::with_space(Chain::new(prog, 0), space.clone());
+                        let s = if prune { s.with_pruning() } else { s };
+                        s.best(objective).unwrap().unwrap()
+                    };
+                    assert_eq!(s(true), s(false));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use super::Error;
+
+/// How an encoder hides each pixel among the layers.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Transparency {
+    /// Each pixel shows through on a layer chosen by its diagonal, and every layer beneath that
+    /// holds the opposite colour.
+    Structured,
+    /// Each pixel shows through on a layer chosen by a generator seeded with the value, and the
+    /// layers beneath hold random pixels.
+    Random(u64),
+}
+
+// SplitMix64, which is good enough to scatter pixels and is fine with any seed.
+struct Rng(u64);
+
+impl Rng {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        ((z ^ (z >> 31)) % n as u64) as usize
+    }
+}
+
+/// Builds Space Image Format data which decodes to a given picture.
+pub struct Encoder {
+    layers: usize,
+    transparency: Transparency,
+    checksum: Option<usize>,
+}
+
+impl Encoder {
+    pub fn new(layers: usize) -> Self {
+        assert!(layers > 0);
+        Encoder {
+            layers,
+            transparency: Transparency::Structured,
+            checksum: None,
+        }
+    }
+
+    pub fn with_transparency(mut self, transparency: Transparency) -> Self {
+        self.transparency = transparency;
+        self
+    }
+
+    /// Arranges for `layer` to be the one with the fewest zeros, and so the one whose digits
+    /// make up the checksum.
+    pub fn with_checksum_layer(mut self, layer: usize) -> Self {
+        assert!(layer < self.layers);
+        self.checksum = Some(layer);
+        self
+    }
+
+    /// Encodes the picture given by `rows`, in which white pixels are true.
+    ///
+    /// This fails if the rows differ in length, or if the checksum layer can't have the fewest
+    /// zeros, which happens when the picture has too few black pixels to put zeros in every
+    /// other layer.
+    pub fn encode<R: AsRef<[bool]>>(&self, rows: &[R]) -> Result<Vec<u8>, Error> {
+        let width = rows.first().map_or(0, |r| r.as_ref().len());
+        if let Some(y) = rows.iter().position(|r| r.as_ref().len() != width) {
+            return Err(Error::UnevenRows(y));
+        }
+        let pixels: Vec<bool> = rows.iter().flat_map(|r| r.as_ref().to_vec()).collect();
+        let size = pixels.len();
+        if size == 0 {
+            return Err(Error::EmptyLayer);
+        }
+
+        let mut rng = match self.transparency {
+            Transparency::Random(seed) => Some(Rng(seed)),
+            Transparency::Structured => None,
+        };
+        let mut data = vec![b'2'; size * self.layers];
+        // The layer on which each pixel shows through.
+        let mut depths = Vec::with_capacity(size);
+        for (i, &white) in pixels.iter().enumerate() {
+            let mut depth = match rng {
+                Some(ref mut rng) => rng.below(self.layers),
+                None => (i % width + i / width) % self.layers,
+            };
+            // A black pixel would add a zero to the checksum layer, so it shows through elsewhere.
+            if !white && self.checksum == Some(depth) && self.layers > 1 {
+                depth = (depth + 1) % self.layers;
+            }
+            depths.push(depth);
+            data[depth * size + i] = if white { b'1' } else { b'0' };
+            for layer in depth + 1..self.layers {
+                let hidden = match rng {
+                    Some(ref mut rng) => b"012"[rng.below(3)],
+                    None if white => b'0',
+                    None => b'1',
+                };
+                data[layer * size + i] = match (self.checksum, hidden) {
+                    (Some(c), b'0') if c == layer => b'2',
+                    _ => hidden,
+                };
+            }
+        }
+
+        if let Some(checksum) = self.checksum {
+            // The checksum layer has no zeros, so it's picked as long as every other layer has at
+            // least one, whichever way ties would be broken.
+            for layer in (0..self.layers).filter(|&l| l != checksum) {
+                let zeros = &data[layer * size..(layer + 1) * size];
+                if zeros.contains(&b'0') {
+                    continue;
+                }
+                // Either a pixel is already hidden here, or a black pixel can show through here
+                // instead of further down.
+                let i = (0..size)
+                    .find(|&i| depths[i] < layer)
+                    .or_else(|| (0..size).find(|&i| !pixels[i] && depths[i] > layer))
+                    .ok_or(Error::ChecksumLayer(checksum))?;
+                depths[i] = depths[i].min(layer);
+                data[layer * size + i] = b'0';
+            }
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Colour, Error, Image};
+    use super::{Encoder, Transparency};
+
+    const PICTURE: [&str; 3] = ["#..##", ".#..#", "##.#."];
+
+    fn picture() -> Vec<Vec<bool>> {
+        PICTURE
+            .iter()
+            .map(|r| r.bytes().map(|b| b == b'#').collect())
+            .collect()
+    }
+
+    // The index of the layer with the fewest zeros, provided no other layer has as few.
+    fn checksum_layer(img: &Image) -> Option<usize> {
+        let zeros: Vec<_> = img.iter().map(|l| l.histogram()[Colour::Black]).collect();
+        let min = *zeros.iter().min()?;
+        match zeros.iter().filter(|&&z| z == min).count() {
+            1 => zeros.iter().position(|&z| z == min),
+            _ => None,
+        }
+    }
+
+    fn decodes(data: &[u8]) -> Image {
+        let img = Image::new(5, 3, data).unwrap();
+        let expected: Vec<u8> = PICTURE
+            .iter()
+            .flat_map(|r| {
+                r.bytes()
+                    .map(|b| if b == b'#' { b'1' } else { b' ' })
+                    .chain(Some(b'\n'))
+            })
+            .collect();
+        assert_eq!(img.image(), expected);
+        img
+    }
+
+    #[test]
+    fn structured() {
+        let data = Encoder::new(4).encode(&picture()).unwrap();
+        assert_eq!(data.len(), 60);
+        assert_eq!(&data[..5], b"12221");
+        // The black pixel at (1, 0) shows through on the second layer, and is white beneath.
+        let column: Vec<u8> = (0..4).map(|l| data[l * 15 + 1]).collect();
+        assert_eq!(column, b"2011");
+        decodes(&data);
+    }
+
+    #[test]
+    fn random() {
+        for seed in 0..20 {
+            let enc = Encoder::new(6).with_transparency(Transparency::Random(seed));
+            let data = enc.encode(&picture()).unwrap();
+            decodes(&data);
+            assert_eq!(data, enc.encode(&picture()).unwrap());
+        }
+        let a = Encoder::new(6).with_transparency(Transparency::Random(1));
+        let b = Encoder::new(6).with_transparency(Transparency::Random(2));
+        assert_ne!(a.encode(&picture()), b.encode(&picture()));
+    }
+
+    #[test]
+    fn checksum() {
+        for layers in 1..6 {
+            for layer in 0..layers {
+                for t in (0..10)
+                    .map(Transparency::Random)
+                    .chain(Some(Transparency::Structured))
+                {
+                    let enc = Encoder::new(layers)
+                        .with_transparency(t)
+                        .with_checksum_layer(layer);
+                    let img = decodes(&enc.encode(&picture()).unwrap());
+                    assert_eq!(checksum_layer(&img), Some(layer));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let enc = Encoder::new(3);
+        assert_eq!(
+            enc.encode(&[vec![true; 2], vec![true]]),
+            Err(Error::UnevenRows(1))
+        );
+        assert_eq!(enc.encode::<Vec<bool>>(&[]), Err(Error::EmptyLayer));
+        let enc = Encoder::new(3).with_checksum_layer(2);
+        assert_eq!(enc.encode(&[[true; 4]]), Err(Error::ChecksumLayer(2)));
+        assert!(Encoder::new(3)
+            .with_checksum_layer(0)
+            .encode(&[[true; 4]])
+            .is_ok());
+    }
+}
@@ -1,6 +1,88 @@
+use crate::ocr;
 use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io;
 use std::ops::Index;
 
+mod encode;
+mod render;
+
+pub use self::encode::{Encoder, Transparency};
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Error {
+    EmptyLayer,
+    TruncatedLayer(usize),
+    InvalidPixel(usize),
+    UnevenRows(usize),
+    ChecksumLayer(usize),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::EmptyLayer => write!(f, "Layers must have at least one pixel"),
+            Error::TruncatedLayer(off) => write!(f, "Truncated layer at offset {}", off),
+            Error::InvalidPixel(off) => write!(f, "Invalid pixel at offset {}", off),
+            Error::UnevenRows(row) => write!(f, "Row {} differs in length from the first", row),
+            Error::ChecksumLayer(layer) => {
+                write!(
+                    f,
+                    "Too few black pixels to take the checksum from layer {}",
+                    layer
+                )
+            }
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(val: Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, val)
+    }
+}
+
+/// The colour of a pixel, given by its digit.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Colour {
+    Black,
+    White,
+    Transparent,
+    /// A digit other than the three with a meaning, which is drawn as white.
+    Other(u8),
+}
+
+impl Colour {
+    fn new(b: u8) -> Self {
+        match b {
+            b'0' => Colour::Black,
+            b'1' => Colour::White,
+            b'2' => Colour::Transparent,
+            _ => Colour::Other(b - b'0'),
+        }
+    }
+
+    pub fn digit(self) -> u8 {
+        match self {
+            Colour::Black => 0,
+            Colour::White => 1,
+            Colour::Transparent => 2,
+            Colour::Other(d) => d,
+        }
+    }
+}
+
+/// A pixel of the decoded picture.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Pixel {
+    pub colour: Colour,
+    /// The layer the colour comes from, or None if the pixel is transparent in every layer.
+    pub layer: Option<usize>,
+}
+
 pub struct Image {
     width: usize,
     height: usize,
@@ -8,22 +90,68 @@ pub struct Image {
 }
 
 impl Image {
-    pub fn new(width: usize, height: usize, data: &[u8]) -> Self {
-        Image {
+    /// Splits `data` into layers of `width` by `height` pixels.
+    ///
+    /// Every pixel must be a decimal digit, and the last layer must be complete.
+    pub fn new(width: usize, height: usize, data: &[u8]) -> Result<Self, Error> {
+        let size = width * height;
+        if size == 0 {
+            return Err(Error::EmptyLayer);
+        }
+        if let Some(off) = data.iter().position(|b| !b.is_ascii_digit()) {
+            return Err(Error::InvalidPixel(off));
+        }
+        let partial = data.len() % size;
+        if partial != 0 {
+            return Err(Error::TruncatedLayer(data.len() - partial));
+        }
+        Ok(Image {
             width,
             height,
-            layers: data.chunks(width * height).map(|c| Layer::new(c)).collect(),
-        }
+            layers: data.chunks(size).map(Layer::new).collect(),
+        })
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &Layer> {
         self.layers.iter()
     }
 
+    /// The checksum from part 1: the number of white pixels times the number of transparent ones
+    /// in the layer with the fewest black pixels.  If several layers tie, this is the smallest of
+    /// their checksums.
+    pub fn checksum(&self) -> Option<usize> {
+        self.layers
+            .iter()
+            .map(Layer::histogram)
+            .map(|h| (h[Colour::Black], h[Colour::White] * h[Colour::Transparent]))
+            .min()
+            .map(|(_, checksum)| checksum)
+    }
+
+    /// The pixel at column `x` of row `y` of the decoded picture.
+    pub fn pixel(&self, x: usize, y: usize) -> Pixel {
+        assert!(x < self.width && y < self.height);
+        self.pixel_at(y * self.width + x)
+    }
+
+    /// The pixels of the decoded picture a row at a time, each with the layer it shows through
+    /// from.
+    pub fn composite(&self) -> Vec<Pixel> {
+        (0..self.width * self.height)
+            .map(|i| self.pixel_at(i))
+            .collect()
+    }
+
+    /// Draws the decoded picture as text, with black as a space and white as '1'.
+    ///
+    /// Pixels which are transparent in every layer are left as '2'.
     pub fn image(&self) -> Vec<u8> {
         (0..(self.width * self.height))
             .flat_map(|i| {
-                let x = self.pixel_at(i);
+                let x = match self.pixel_at(i).colour {
+                    Colour::Black => b' ',
+                    c => b'0' + c.digit(),
+                };
                 if i % self.width == (self.width - 1) {
                     vec![x, b'\n']
                 } else {
@@ -33,14 +161,32 @@ impl Image {
             .collect()
     }
 
-    fn pixel_at(&self, i: usize) -> u8 {
+    /// Reads the letters drawn by the white pixels of the decoded picture.
+    pub fn text(&self) -> Result<ocr::Reading, ocr::Error> {
+        let rows: Vec<Vec<bool>> = (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| self.pixel_at(y * self.width + x).colour == Colour::White)
+                    .collect()
+            })
+            .collect();
+        ocr::read(&rows)
+    }
+
+    // Pixel `i` from the first layer where it isn't transparent.
+    fn pixel_at(&self, i: usize) -> Pixel {
         self.layers
             .iter()
-            .map(|l| l[i])
-            .filter(|&c| c != b'2')
-            .nth(0)
-            .map(|c| if c == b'0' { b' ' } else { c })
-            .unwrap()
+            .enumerate()
+            .map(|(n, l)| Pixel {
+                colour: l.colour(i),
+                layer: Some(n),
+            })
+            .find(|p| p.colour != Colour::Transparent)
+            .unwrap_or(Pixel {
+                colour: Colour::Transparent,
+                layer: None,
+            })
     }
 }
 
@@ -55,6 +201,19 @@ impl Layer {
         }
     }
 
+    pub fn colour(&self, i: usize) -> Colour {
+        Colour::new(self.contents[i])
+    }
+
+    /// Counts the pixels of each colour.
+    pub fn histogram(&self) -> Histogram {
+        let mut counts = [0; 10];
+        for b in &self.contents {
+            counts[(b - b'0') as usize] += 1;
+        }
+        Histogram { counts }
+    }
+
     pub fn summary(&self) -> HashMap<u8, usize> {
         let mut map = HashMap::new();
         for b in &self.contents {
@@ -76,13 +235,26 @@ impl Index<usize> for Layer {
     }
 }
 
+/// The number of pixels of each colour in a layer.
+pub struct Histogram {
+    counts: [usize; 10],
+}
+
+impl Index<Colour> for Histogram {
+    type Output = usize;
+
+    fn index(&self, colour: Colour) -> &usize {
+        self.counts.index(colour.digit() as usize)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Image;
+    use super::{Colour, Error, Image, Pixel};
 
     #[test]
     fn layer() {
-        let img = Image::new(3, 2, b"123456789012");
+        let img = Image::new(3, 2, b"123456789012").unwrap();
         let summary = img.iter().map(|l| l.summary()).collect::<Vec<_>>();
         assert_eq!(summary[0].get(&b'1'), Some(&1));
         assert_eq!(summary[0].get(&b'6'), Some(&1));
@@ -95,7 +267,77 @@ mod tests {
 
     #[test]
     fn image() {
-        let img = Image::new(2, 2, b"0222112222120000");
+        let img = Image::new(2, 2, b"0222112222120000").unwrap();
         assert_eq!(img.image(), b" 1\n1 \n");
+        let img = Image::new(2, 2, b"02221212").unwrap();
+        assert_eq!(img.image(), b" 2\n12\n");
+    }
+
+    #[test]
+    fn checksum() {
+        let img = Image::new(3, 2, b"123456789012").unwrap();
+        let h = img.iter().next().unwrap().histogram();
+        assert_eq!(h[Colour::White], 1);
+        assert_eq!(h[Colour::Other(6)], 1);
+        assert_eq!(h[Colour::Black], 0);
+        assert_eq!(img.checksum(), Some(1));
+        // The second layer has no zeros, which the first layer's two can't beat.
+        let img = Image::new(2, 2, b"001211220000").unwrap();
+        assert_eq!(img.checksum(), Some(4));
+        // Both layers have one zero, and the second has the smaller checksum.
+        let img = Image::new(2, 2, b"01120222").unwrap();
+        assert_eq!(img.checksum(), Some(0));
+        assert_eq!(Image::new(2, 2, b"").unwrap().checksum(), None);
+    }
+
+    #[test]
+    fn composite() {
+        let img = Image::new(2, 2, b"02221212").unwrap();
+        let px = |colour, layer| Pixel { colour, layer };
+        assert_eq!(
+            img.composite(),
+            vec![
+                px(Colour::Black, Some(0)),
+                px(Colour::Transparent, None),
+                px(Colour::White, Some(1)),
+                px(Colour::Transparent, None),
+            ]
+        );
+        assert_eq!(img.pixel(0, 1), px(Colour::White, Some(1)));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(Image::new(0, 6, b"").err(), Some(Error::EmptyLayer));
+        assert_eq!(
+            Image::new(2, 2, b"012210").err(),
+            Some(Error::TruncatedLayer(4))
+        );
+        assert_eq!(
+            Image::new(2, 2, b"0122\n").err(),
+            Some(Error::InvalidPixel(4))
+        );
+        assert_eq!(Image::new(2, 2, b"").unwrap().image(), b"22\n22\n");
+    }
+
+    #[test]
+    fn text() {
+        // The top layer draws an A and leaves the rest transparent, and the one beneath draws a B
+        // beside it.
+        let top = [".##.", "#..#", "#..#", "####", "#..#", "#..#"];
+        let under = ["###.", "#..#", "###.", "#..#", "#..#", "###."];
+        let mut data = Vec::new();
+        for row in &top {
+            data.extend(row.bytes().map(|b| if b == b'#' { b'1' } else { b'0' }));
+            data.extend(b"222222");
+        }
+        for row in &under {
+            data.extend(b"11110");
+            data.extend(row.bytes().map(|b| if b == b'#' { b'1' } else { b'0' }));
+            data.push(b'0');
+        }
+        let r = Image::new(10, 6, &data).unwrap().text().unwrap();
+        assert_eq!(r.text, "AB");
+        assert!(r.is_complete());
     }
 }
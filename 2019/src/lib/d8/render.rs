@@ -0,0 +1,221 @@
+use super::{Colour, Image};
+use std::io::{self, Write};
+
+// The levels of grey used for the pixels of each colour, with transparency as mid-grey.
+const BLACK: u8 = 0;
+const WHITE: u8 = 255;
+const GREY: u8 = 128;
+
+/// Computes CRC-32 as used by PNG and zlib's container.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &x in data {
+        a = (a + x as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// Wraps `data` in a zlib stream of stored (uncompressed) deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX: usize = 65535;
+    let mut v = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX).peekable();
+    if blocks.peek().is_none() {
+        v.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none() as u8;
+        let len = block.len() as u16;
+        v.push(last);
+        v.extend_from_slice(&len.to_le_bytes());
+        v.extend_from_slice(&(!len).to_le_bytes());
+        v.extend_from_slice(block);
+    }
+    v.extend_from_slice(&adler32(data).to_be_bytes());
+    v
+}
+
+fn chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    let mut body = kind.to_vec();
+    body.extend_from_slice(data);
+    w.write_all(&body)?;
+    w.write_all(&crc32(&body).to_be_bytes())
+}
+
+impl Image {
+    // The rows of the decoded picture scaled up by `scale`, as levels of grey.
+    fn scaled(&self, scale: usize) -> Vec<Vec<u8>> {
+        (0..self.height * scale)
+            .map(|y| {
+                (0..self.width * scale)
+                    .map(
+                        |x| match self.pixel_at(y / scale * self.width + x / scale).colour {
+                            Colour::Black => BLACK,
+                            Colour::Transparent => GREY,
+                            _ => WHITE,
+                        },
+                    )
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Writes the decoded picture as a plain PBM bitmap, with each pixel `scale` bitmap pixels
+    /// on a side.
+    ///
+    /// As in the puzzle, 0 is black and 1 is white.  A bitmap has no other colours, so fully
+    /// transparent pixels are left white.
+    pub fn write_pbm<W: Write>(&self, w: &mut W, scale: usize) -> io::Result<()> {
+        writeln!(w, "P1")?;
+        writeln!(w, "{} {}", self.width * scale, self.height * scale)?;
+        for row in self.scaled(scale) {
+            let bits: Vec<_> = row
+                .iter()
+                .map(|&g| if g == BLACK { "1" } else { "0" })
+                .collect();
+            writeln!(w, "{}", bits.join(" "))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the decoded picture as a plain PGM greymap, with fully transparent pixels in grey.
+    pub fn write_pgm<W: Write>(&self, w: &mut W, scale: usize) -> io::Result<()> {
+        writeln!(w, "P2")?;
+        writeln!(w, "{} {}", self.width * scale, self.height * scale)?;
+        writeln!(w, "255")?;
+        for row in self.scaled(scale) {
+            let levels: Vec<_> = row.iter().map(|g| g.to_string()).collect();
+            writeln!(w, "{}", levels.join(" "))?;
+        }
+        Ok(())
+    }
+
+    /// Writes the decoded picture as an uncompressed greyscale PNG, in which fully transparent
+    /// pixels are transparent.
+    pub fn write_png<W: Write>(&self, w: &mut W, scale: usize) -> io::Result<()> {
+        let (width, height) = (self.width * scale, self.height * scale);
+        w.write_all(b"\x89PNG\r\n\x1a\n")?;
+        let mut header = Vec::new();
+        header.extend_from_slice(&(width as u32).to_be_bytes());
+        header.extend_from_slice(&(height as u32).to_be_bytes());
+        // Eight bits per sample, grey with alpha, and the only defined compression, filter, and
+        // (no) interlace methods.
+        header.extend_from_slice(&[8, 4, 0, 0, 0]);
+        chunk(w, b"IHDR", &header)?;
+        let mut raw = Vec::with_capacity(height * (1 + 2 * width));
+        for row in self.scaled(scale) {
+            // Each row starts with its filter type, which is none.
+            raw.push(0);
+            for g in row {
+                raw.extend_from_slice(&[g, if g == GREY { 0 } else { 255 }]);
+            }
+        }
+        chunk(w, b"IDAT", &zlib_stored(&raw))?;
+        chunk(w, b"IEND", &[])
+    }
+
+    /// Writes the decoded picture as SVG, drawing each pixel as a square `scale` units on a side.
+    ///
+    /// Fully transparent pixels are left out.
+    pub fn write_svg<W: Write>(&self, w: &mut W, scale: usize) -> io::Result<()> {
+        writeln!(
+            w,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" shape-rendering=\"crispEdges\">",
+            self.width * scale,
+            self.height * scale
+        )?;
+        for i in 0..self.width * self.height {
+            let fill = match self.pixel_at(i).colour {
+                Colour::Black => "black",
+                Colour::Transparent => continue,
+                _ => "white",
+            };
+            writeln!(
+                w,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                i % self.width * scale,
+                i / self.width * scale,
+                scale,
+                scale,
+                fill
+            )?;
+        }
+        writeln!(w, "</svg>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Image;
+    use super::{adler32, crc32};
+
+    fn image() -> Image {
+        Image::new(2, 2, b"0222112222120000").unwrap()
+    }
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn netpbm() {
+        let mut v = Vec::new();
+        image().write_pbm(&mut v, 1).unwrap();
+        assert_eq!(v, b"P1\n2 2\n1 0\n0 1\n");
+        let mut v = Vec::new();
+        image().write_pgm(&mut v, 2).unwrap();
+        assert_eq!(
+            String::from_utf8(v).unwrap(),
+            "P2\n4 4\n255\n0 0 255 255\n0 0 255 255\n255 255 0 0\n255 255 0 0\n"
+        );
+    }
+
+    #[test]
+    fn png() {
+        let mut v = Vec::new();
+        image().write_png(&mut v, 3).unwrap();
+        assert_eq!(&v[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&v[12..16], b"IHDR");
+        assert_eq!(&v[16..24], &[0, 0, 0, 6, 0, 0, 0, 6]);
+        assert_eq!(&v[v.len() - 8..v.len() - 4], b"IEND");
+        // Each chunk's CRC covers its type and data.
+        let len = u32::from_be_bytes([v[8], v[9], v[10], v[11]]) as usize;
+        let crc = &v[16 + len..20 + len];
+        assert_eq!(crc, &crc32(&v[12..16 + len]).to_be_bytes());
+        // The image data is the 6 rows of 1 + 6 * 2 bytes, stored as-is.
+        let idat = 20 + len;
+        assert_eq!(&v[idat + 4..idat + 8], b"IDAT");
+        let idat_len = u32::from_be_bytes([v[idat], v[idat + 1], v[idat + 2], v[idat + 3]]);
+        assert_eq!(idat_len as usize, 2 + 5 + 6 * 13 + 4);
+        assert_eq!(&v[idat + 15..idat + 18], &[0, 0, 255]);
+    }
+
+    #[test]
+    fn svg() {
+        let mut v = Vec::new();
+        image().write_svg(&mut v, 10).unwrap();
+        let s = String::from_utf8(v).unwrap();
+        assert!(
+            s.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"20\"")
+        );
+        assert!(s.contains("<rect x=\"10\" y=\"0\" width=\"10\" height=\"10\" fill=\"white\"/>"));
+        assert_eq!(s.matches("fill=\"black\"").count(), 2);
+        assert!(s.trim_end().ends_with("</svg>"));
+    }
+}
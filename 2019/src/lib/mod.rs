@@ -11,3 +11,4 @@ pub mod d8;
 pub mod d10;
 pub mod d12;
 pub mod d13;
+pub mod ocr;
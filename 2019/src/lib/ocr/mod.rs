@@ -0,0 +1,192 @@
+//! Recognition of the block capitals that some puzzles draw as their answer.
+//!
+//! The letters are four pixels wide and six high, with a blank column between them, so each one
+//! takes up five columns.  A few letters, such as Y, use the fifth column as well.
+
+use std::error;
+use std::fmt;
+use std::io;
+
+pub const HEIGHT: usize = 6;
+const ADVANCE: usize = 5;
+
+const GLYPHS: &[(char, [&str; HEIGHT])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum Error {
+    WrongHeight(usize),
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Error::WrongHeight(h) => {
+                write!(f, "Expected letters {} pixels high, not {}", HEIGHT, h)
+            }
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(val: Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, val)
+    }
+}
+
+/// The text read from a picture.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Reading {
+    /// The letters read, with '?' for each glyph that isn't a known letter and a space for each
+    /// blank one.
+    pub text: String,
+    /// The positions in `text` of the glyphs that weren't recognised.
+    pub unknown: Vec<usize>,
+}
+
+impl Reading {
+    /// Whether every glyph was recognised.
+    pub fn is_complete(&self) -> bool {
+        self.unknown.is_empty()
+    }
+}
+
+/// Reads the letters drawn by the lit pixels of `rows`.
+///
+/// Blank rows above and below the letters, and blank columns either side of them, are ignored,
+/// so any picture with the text somewhere inside it can be read.  Since some letters start with
+/// a blank column, each possible alignment of the glyphs is tried, and the one which recognises
+/// the most letters wins.
+pub fn read<R: AsRef<[bool]>>(rows: &[R]) -> Result<Reading, Error> {
+    let lit = |r: &R| r.as_ref().iter().any(|&p| p);
+    let top = rows.iter().position(lit).unwrap_or(rows.len());
+    let bottom = rows.iter().rposition(lit).map_or(top, |b| b + 1);
+    let rows: Vec<&[bool]> = rows[top..bottom].iter().map(|r| r.as_ref()).collect();
+    if rows.is_empty() {
+        return Ok(Reading {
+            text: String::new(),
+            unknown: Vec::new(),
+        });
+    }
+    if rows.len() != HEIGHT {
+        return Err(Error::WrongHeight(rows.len()));
+    }
+
+    let first = rows
+        .iter()
+        .filter_map(|r| r.iter().position(|&p| p))
+        .min()
+        .unwrap();
+    let last = rows
+        .iter()
+        .filter_map(|r| r.iter().rposition(|&p| p))
+        .max()
+        .unwrap();
+    Ok((0..ADVANCE as isize)
+        .map(|shift| read_from(&rows, first as isize - shift, last as isize))
+        .min_by_key(|r| r.unknown.len())
+        .unwrap())
+}
+
+// Reads glyphs starting at column `start` until they pass column `last`.
+fn read_from(rows: &[&[bool]], start: isize, last: isize) -> Reading {
+    let pixel = |y: usize, x: isize| x >= 0 && rows[y].get(x as usize) == Some(&true);
+    let mut reading = Reading {
+        text: String::new(),
+        unknown: Vec::new(),
+    };
+    let mut x = start;
+    while x <= last {
+        let cell = |y: usize, dx: usize| pixel(y, x + dx as isize);
+        let blank = (0..HEIGHT).all(|y| (0..ADVANCE).all(|dx| !cell(y, dx)));
+        let glyph = GLYPHS.iter().find(|(_, g)| {
+            g.iter().enumerate().all(|(y, row)| {
+                (0..ADVANCE).all(|dx| cell(y, dx) == (row.as_bytes().get(dx) == Some(&b'#')))
+            })
+        });
+        match glyph {
+            _ if blank => reading.text.push(' '),
+            Some((c, _)) => reading.text.push(*c),
+            None => {
+                reading.unknown.push(reading.text.len());
+                reading.text.push('?');
+            }
+        }
+        x += ADVANCE as isize;
+    }
+    reading
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read, Error, Reading, GLYPHS};
+
+    // Draws `text` with the font, offset by `left` blank columns.
+    fn draw(text: &str, left: usize) -> Vec<Vec<bool>> {
+        (0..6)
+            .map(|y| {
+                let mut row = vec![false; left];
+                for c in text.chars() {
+                    let glyph = GLYPHS.iter().find(|(g, _)| *g == c).unwrap().1[y];
+                    let mut cell: Vec<_> = glyph.bytes().map(|b| b == b'#').collect();
+                    cell.resize(5, false);
+                    row.extend(cell);
+                }
+                row
+            })
+            .collect()
+    }
+
+    #[test]
+    fn letters() {
+        let all: String = GLYPHS.iter().map(|(c, _)| c).collect();
+        let r = read(&draw(&all, 0)).unwrap();
+        assert_eq!(r.text, all);
+        assert!(r.is_complete());
+        assert_eq!(read(&draw("JAIL", 1)).unwrap().text, "JAIL");
+        assert_eq!(read(&draw("YES", 3)).unwrap().text, "YES");
+    }
+
+    #[test]
+    fn margins() {
+        let mut grid = draw("HULL", 2);
+        grid.insert(0, vec![false; 30]);
+        grid.push(vec![false; 10]);
+        assert_eq!(read(&grid).unwrap().text, "HULL");
+        assert_eq!(read(&[[false; 4]; 3]).unwrap().text, "");
+    }
+
+    #[test]
+    fn unknown() {
+        let mut grid = draw("CAB", 0);
+        grid[0][5] = true;
+        assert_eq!(
+            read(&grid).unwrap(),
+            Reading {
+                text: "C?B".into(),
+                unknown: vec![1],
+            }
+        );
+        assert_eq!(read(&grid[..5]), Err(Error::WrongHeight(5)));
+    }
+}